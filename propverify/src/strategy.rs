@@ -175,7 +175,6 @@ macro_rules! proptest {
       $(#[cfg_attr(not(crux), $meta)])*
       fn $test_name() {
           $(
-              #[cfg(not(crux))]
               {
                   let str = stringify!($meta);
                   if str.starts_with("should_panic") {
@@ -183,7 +182,9 @@ macro_rules! proptest {
                   }
               }
           )*
-          $(let $parm = $crate::prelude::Strategy::value(&$strategy);)*
+          // Name the symbolic values after the parameters
+          // (so that KLEE's .ktest files are easier to understand)
+          $(let $parm = verifier::with_name(std::stringify!($parm), || $crate::prelude::Strategy::value(&$strategy));)*
 
           #[cfg(not(crux))]
           if prop_is_replay() {
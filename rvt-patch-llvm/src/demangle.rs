@@ -0,0 +1,129 @@
+////////////////////////////////////////////////////////////////
+// Demangling Rust symbol names
+//
+// Functions are selected using their Rust paths (e.g., 'mycrate::tests::t1')
+// instead of their mangled names so that selection works with both
+// the legacy and the v0 mangling schemes.
+////////////////////////////////////////////////////////////////
+
+/// Demangle a Rust symbol (legacy or v0 mangling) into a path like
+/// 'std::io::stdio::_print'.
+///
+/// The hash (legacy), crate disambiguators (v0) and generic arguments
+/// are omitted so that all instances of a generic function have the same path.
+pub fn demangled_path(symbol: &str) -> Option<String> {
+    let demangled = rustc_demangle::try_demangle(symbol).ok()?;
+    Some(strip_generic_args(&format!("{:#}", demangled)))
+}
+
+/// Test whether 'path' is selected by 'selector'.
+///
+/// A selector is either a full path or a suffix of a path that
+/// starts at a path segment (e.g., 'tests::t1' selects 'mycrate::tests::t1').
+pub fn path_matches(path: &str, selector: &str) -> bool {
+    path == selector
+        || (path.ends_with(selector) && path[..path.len() - selector.len()].ends_with("::"))
+}
+
+/// Remove generic arguments like '::<u32>' from a path.
+///
+/// Angle brackets that are not preceded by '::' are part of
+/// the path (e.g., '<T as Trait>::f') and are kept.
+fn strip_generic_args(path: &str) -> String {
+    let mut r = String::new();
+    let mut depth = 0;
+    let mut rest = path;
+    while let Some(c) = rest.chars().next() {
+        if depth == 0 && rest.starts_with("::<") {
+            depth = 1;
+            rest = &rest[3..];
+            continue;
+        }
+        if depth > 0 {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => (),
+            }
+        } else {
+            r.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy() {
+        assert_eq!(demangled_path("_ZN1g3foo17hd49332c63e22c8c0E").unwrap(), "g::foo");
+        assert_eq!(
+            demangled_path(
+                "_ZN91_$LT$std..panicking..begin_panic..PanicPayload$LT$A$GT$$u20$as$u20$core..panic..BoxMeUp$GT$8take_box17h7029775f73cc9e1fE"
+            )
+            .unwrap(),
+            "<std::panicking::begin_panic::PanicPayload<A> as core::panic::BoxMeUp>::take_box"
+        );
+        assert_eq!(
+            demangled_path("_ZN1t3old2sp28_$u7b$$u7b$closure$u7d$$u7d$17hdd12530d791b6be9E").unwrap(),
+            "t::old::sp::{{closure}}"
+        );
+    }
+
+    #[test]
+    fn v0() {
+        // g::foo::<alloc::vec::Vec<u8>>
+        assert_eq!(
+            demangled_path("_RINvCsaRyNK4lDSkl_1g3fooINtNtCsbEht8wFNRx7_5alloc3vec3VechEEB2_").unwrap(),
+            "g::foo"
+        );
+        // std::rt::lang_start::<()>
+        assert_eq!(
+            demangled_path("_RINvNtCsi4IsKQVxMg0_3std2rt10lang_startuECsaRyNK4lDSkl_1g").unwrap(),
+            "std::rt::lang_start"
+        );
+        assert_eq!(
+            demangled_path(
+                "_RNvYNCNvNtCskOGwlKjLXpR_1t3new2sp0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_"
+            )
+            .unwrap(),
+            "<t::new::sp::{closure#0} as core::ops::function::FnOnce<()>>::call_once"
+        );
+    }
+
+    #[test]
+    fn not_rust() {
+        assert_eq!(demangled_path("main"), None);
+        assert_eq!(demangled_path("klee_make_symbolic"), None);
+    }
+
+    #[test]
+    fn generic_args() {
+        assert_eq!(strip_generic_args("a::f::<u32>"), "a::f");
+        assert_eq!(strip_generic_args("a::S::<Vec<Option<u8>>>::new"), "a::S::new");
+        assert_eq!(strip_generic_args("<a::S<T> as a::Tr>::f::<u8>"), "<a::S<T> as a::Tr>::f");
+        assert_eq!(strip_generic_args("a::f"), "a::f");
+    }
+
+    #[test]
+    fn suffixes() {
+        assert!(path_matches("mycrate::tests::t1", "mycrate::tests::t1"));
+        assert!(path_matches("mycrate::tests::t1", "tests::t1"));
+        assert!(path_matches("mycrate::tests::t1", "t1"));
+        // Suffixes must start at a path segment
+        assert!(!path_matches("mycrate::tests::t1", "sts::t1"));
+        assert!(!path_matches("mycrate::mytests::t1", "tests::t1"));
+        assert!(!path_matches("mycrate::tests::t10", "tests::t1"));
+        // A suffix also selects paths in other crates
+        // (which `find_function` reports as ambiguous)
+        assert!(path_matches("othercrate::tests::t1", "tests::t1"));
+        assert!(!path_matches("othercrate::tests::t1", "mycrate::tests::t1"));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,397 @@
+////////////////////////////////////////////////////////////////
+// Finding the #[test] functions in a module
+//
+// When compiling tests, rustc generates a descriptor for each
+// #[test] function and the test harness runs the tests listed in
+// the descriptors. The layout of the descriptors changes between
+// rustc versions so, instead of decoding them, we look for any
+// global that refers to both
+//
+// - a closure 'path::{{closure}}' (that runs the test 'path')
+//   or a 'FnOnce::call_once' shim that calls the closure
+// - a string containing the name of the test
+//   (the path without the crate name)
+//
+// The only field that we decode is 'should_panic'. Its type is
+//
+//     enum ShouldPanic { No, Yes, YesWithMessage(&'static str) }
+//
+// whose layout does not depend on how rustc orders the fields of the
+// descriptor: an 8-byte tag followed by the pointer and length of the
+// message (see `should_panic`). We assume 64-bit pointers.
+////////////////////////////////////////////////////////////////
+
+use inkwell::module::Module;
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::{AnyValue, BasicValueEnum, FunctionValue, GlobalValue, InstructionOpcode};
+use log::info;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::demangle::demangled_path;
+use crate::error::{Error, Result};
+use crate::functions::get_functions_with_path;
+use crate::llvm;
+
+/// A #[test] function
+#[derive(Serialize)]
+pub struct Entry {
+    /// Path of the test function (e.g., 'mycrate::tests::t1')
+    pub path: String,
+    /// Name of the test used by the test harness (e.g., 'tests::t1')
+    pub name: String,
+    /// Mangled name of the test function (if it has not been inlined)
+    pub symbol: Option<String>,
+    /// Expected failure (if any)
+    pub should_panic: Option<ShouldPanic>,
+}
+
+/// The expected failure of a #[should_panic] test.
+#[derive(Serialize)]
+pub struct ShouldPanic {
+    /// The message given by #[should_panic(expected = "...")] (if any)
+    pub expected: Option<String>,
+}
+
+impl ShouldPanic {
+    /// The expectation in the format used by `verifier::expect_raw`
+    /// (and printed in `VERIFIER_EXPECT:` lines).
+    pub fn expect_raw(&self) -> String {
+        match &self.expected {
+            None => "should_panic".to_string(),
+            Some(expected) => format!("should_panic(expected = \"{}\")", expected),
+        }
+    }
+}
+
+/// Write the expectations of all #[should_panic] tests to 'path'
+/// as a JSON object that maps test names to expectations.
+pub fn write_expectations(module: &Module, path: &Path) -> Result<()> {
+    let expectations: BTreeMap<String, String> = find_entries(module)?
+        .into_iter()
+        .filter_map(|e| Some((e.name, e.should_panic?.expect_raw())))
+        .collect();
+    let json = serde_json::to_string_pretty(&expectations)?;
+    std::fs::write(path, json).map_err(|e| Error::write(path, e))?;
+    info!(
+        "Wrote {} expectations to {}",
+        expectations.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Find all the #[test] functions in a module
+pub fn find_entries(module: &Module) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut og = module.get_first_global();
+    while let Some(g) = og {
+        if let Some(mut entry) = test_descriptor(module, g) {
+            // The test function itself
+            entry.symbol = get_functions_with_path(module, |p| p == entry.path)?
+                .into_iter()
+                .next()
+                .map(|(_, f)| f.get_name().to_string_lossy().into_owned());
+            entries.push(entry);
+        }
+        og = g.get_next_global();
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Recognize a test descriptor
+///
+/// (The symbol of the test function is filled in by `find_entries`.)
+fn test_descriptor(module: &Module, g: GlobalValue) -> Option<Entry> {
+    let initializer = g.get_initializer()?;
+    let mut names = Vec::new();
+    collect_references(initializer, &mut names);
+
+    // Find the closure that runs the test
+    let path = names.iter().find_map(|n| test_path(module, n))?;
+
+    // The name of the test is the path without the crate name
+    let name = path.split_once("::")?.1.to_string();
+    let is_name = |n: &String| module.get_global(n).and_then(const_string).as_ref() == Some(&name);
+    if !names.iter().any(is_name) {
+        return None;
+    }
+
+    let should_panic = should_panic(module, &descriptor_words(initializer)?);
+
+    Some(Entry {
+        path,
+        name,
+        symbol: None,
+        should_panic,
+    })
+}
+
+/// Collect the names of all globals and functions that a constant refers to
+fn collect_references(v: BasicValueEnum, names: &mut Vec<String>) {
+    if v.is_pointer_value() {
+        if let Ok(name) = v.into_pointer_value().get_name().to_str() {
+            if !name.is_empty() {
+                names.push(name.to_string());
+                return;
+            }
+        }
+    }
+    // Constant expressions (e.g., bitcasts) and aggregates
+    for op in llvm::operands(v) {
+        collect_references(op, names);
+    }
+}
+
+/// An 8-byte word of a descriptor
+enum Word {
+    /// A pointer to a global or a function (or a null pointer)
+    Pointer(Option<String>),
+    /// A known value
+    Value(u64),
+    /// Undefined bytes
+    Undef,
+    /// Partly undefined bytes
+    Unknown,
+}
+
+/// Split a descriptor into 8-byte words.
+///
+/// Returns `None` if the descriptor contains a constant that we do not understand.
+fn descriptor_words(v: BasicValueEnum) -> Option<Vec<Word>> {
+    let mut bytes = Vec::new();
+    let mut pointers = BTreeMap::new();
+    collect_bytes(v, &mut bytes, &mut pointers)?;
+    let words = bytes
+        .chunks(8)
+        .enumerate()
+        .map(|(i, w)| {
+            if let Some(p) = pointers.remove(&(8 * i)) {
+                Word::Pointer(p)
+            } else if w.iter().all(Option::is_none) {
+                Word::Undef
+            } else if w.len() == 8 && w.iter().all(Option::is_some) {
+                Word::Value(
+                    w.iter()
+                        .rev()
+                        .fold(0, |n, b| (n << 8) | u64::from(b.unwrap())),
+                )
+            } else {
+                Word::Unknown
+            }
+        })
+        .collect();
+    Some(words)
+}
+
+/// Collect the bytes of a constant ('None' for undefined bytes)
+/// and the offsets of the pointers in it.
+fn collect_bytes(
+    v: BasicValueEnum,
+    bytes: &mut Vec<Option<u8>>,
+    pointers: &mut BTreeMap<usize, Option<String>>,
+) -> Option<()> {
+    if v.is_pointer_value() {
+        let mut names = Vec::new();
+        collect_references(v, &mut names);
+        pointers.insert(bytes.len(), names.into_iter().next());
+        bytes.extend(vec![None; 8]);
+        return Some(());
+    }
+    // Aggregates
+    let operands = llvm::operands(v);
+    if !operands.is_empty() {
+        for op in operands {
+            collect_bytes(op, bytes, pointers)?;
+        }
+        return Some(());
+    }
+    bytes.extend(const_bytes(v)?);
+    Some(())
+}
+
+/// The bytes of a constant integer or byte array ('None' for undefined bytes)
+///
+/// Byte arrays are decoded from their printed form (e.g., '[8 x i8] c"\02\00..."').
+fn const_bytes(v: BasicValueEnum) -> Option<Vec<Option<u8>>> {
+    let (size, text) = match v {
+        BasicValueEnum::ArrayValue(a) if is_byte(a.get_type().get_element_type()) => {
+            (a.get_type().len() as usize, a.print_to_string())
+        }
+        BasicValueEnum::IntValue(i) if i.get_type().get_bit_width() % 8 == 0 => {
+            let size = (i.get_type().get_bit_width() / 8) as usize;
+            if let Some(n) = i.get_zero_extended_constant() {
+                return Some(
+                    (0..size)
+                        .map(|k| Some(n.checked_shr(8 * k as u32).unwrap_or(0) as u8))
+                        .collect(),
+                );
+            }
+            (size, i.print_to_string())
+        }
+        _ => return None,
+    };
+    let text = text.to_string();
+    if text.ends_with("undef") || text.ends_with("poison") {
+        Some(vec![None; size])
+    } else if text.ends_with("zeroinitializer") {
+        Some(vec![Some(0); size])
+    } else {
+        let s = text.split("c\"").nth(1)?;
+        let bytes = unescape_bytes(&s[..s.rfind('"')?])?;
+        if bytes.len() == size {
+            Some(bytes.into_iter().map(Some).collect())
+        } else {
+            None
+        }
+    }
+}
+
+/// Test whether a type is 'i8'
+fn is_byte(ty: BasicTypeEnum) -> bool {
+    ty.is_int_type() && ty.into_int_type().get_bit_width() == 8
+}
+
+/// Find the expected failure in the 'should_panic' field of a descriptor
+///
+/// The field is a tag (0: No, 1: Yes, 2: YesWithMessage) followed by
+/// the pointer and length of the message (which are undefined or zero
+/// if there is no message). The length of a string is
+/// never the tag (e.g., the length of the name of the test or of
+/// the message in 'ignore_message') so we skip words that follow a pointer.
+fn should_panic(module: &Module, words: &[Word]) -> Option<ShouldPanic> {
+    for i in 0..words.len() {
+        if i > 0 && matches!(words[i - 1], Word::Pointer(_)) {
+            continue;
+        }
+        if let (Word::Value(2), Some(Word::Pointer(Some(message)))) = (&words[i], words.get(i + 1))
+        {
+            if let Some(expected) = module.get_global(message).and_then(const_string) {
+                return Some(ShouldPanic {
+                    expected: Some(expected),
+                });
+            }
+        }
+        // There is no message so the rest of the field is undefined or zero
+        if let (Word::Value(1), Some(w1), Some(w2)) =
+            (&words[i], words.get(i + 1), words.get(i + 2))
+        {
+            match (w1, w2) {
+                (Word::Undef, Word::Undef) | (Word::Value(0), Word::Value(0)) => {
+                    return Some(ShouldPanic { expected: None });
+                }
+                _ => (),
+            }
+        }
+    }
+    None
+}
+
+/// The path of the test run by the function 'name' in a descriptor.
+///
+/// This is either the closure that runs the test or a 'FnOnce::call_once'
+/// shim for the closure. The v0 mangling of the shim names the closure
+/// but, with legacy mangling, we look for the closure that the shim calls.
+/// (Tests are not recognized if the closure is inlined into a legacy shim.)
+fn test_path(module: &Module, name: &str) -> Option<String> {
+    let fun = module.get_function(name)?;
+    let path = demangled_path(name)?;
+    if path == "core::ops::function::FnOnce::call_once" {
+        callees(fun)
+            .iter()
+            .find_map(|n| closure_parent(&demangled_path(n)?))
+    } else {
+        closure_parent(&path)
+    }
+}
+
+/// The names of the functions called by a function
+fn callees(fun: FunctionValue) -> Vec<String> {
+    let mut names = Vec::new();
+    for bb in fun.get_basic_blocks() {
+        let mut oi = bb.get_first_instruction();
+        while let Some(i) = oi {
+            // (The callee is the last operand of calls and invokes.)
+            let n = i.get_num_operands();
+            if n > 0
+                && matches!(
+                    i.get_opcode(),
+                    InstructionOpcode::Call | InstructionOpcode::Invoke
+                )
+            {
+                if let Some(callee) = i.get_operand(n - 1).and_then(|op| op.left()) {
+                    collect_references(callee, &mut names);
+                }
+            }
+            oi = i.get_next_instruction();
+        }
+    }
+    names
+}
+
+/// The path of the function that a closure is defined in
+/// (legacy: 'f::{{closure}}', v0: 'f::{closure#0}').
+///
+/// The v0 shim '<f::{closure#0} as core::ops::function::FnOnce<()>>::call_once'
+/// is treated as the closure.
+fn closure_parent(path: &str) -> Option<String> {
+    if path.starts_with('<') && path.ends_with(">::call_once") {
+        let end = path.find(" as core::ops::function::FnOnce")?;
+        return closure_parent(&path[1..end]);
+    }
+    let i = path.rfind("::")?;
+    let last = &path[i + 2..];
+    if last == "{{closure}}" || last.starts_with("{closure#") {
+        Some(path[..i].to_string())
+    } else {
+        None
+    }
+}
+
+/// The contents of a global that contains a single constant string
+///
+/// We use the printed form of the global
+///
+///     @alloc1 = private unnamed_addr constant <{ [9 x i8] }> <{ [9 x i8] c"tests::t1" }>, align 1
+///
+/// because rustc wraps strings in structs and does not add a NUL terminator.
+fn const_string(g: GlobalValue) -> Option<String> {
+    g.get_initializer()?;
+    let text = g.as_pointer_value().print_to_string().to_string();
+    let mut parts = text.split("c\"");
+    parts.next();
+    let s = parts.next()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let s = &s[..s.find('"')?];
+    unescape(s)
+}
+
+/// Decode an LLVM string literal (where '\XX' is a hex escape)
+fn unescape(s: &str) -> Option<String> {
+    String::from_utf8(unescape_bytes(s)?).ok()
+}
+
+/// Decode the bytes of an LLVM string literal
+fn unescape_bytes(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut rest = s.as_bytes();
+    while let Some((&c, tail)) = rest.split_first() {
+        if c == b'\\' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(c);
+            rest = tail;
+        }
+    }
+    Some(bytes)
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
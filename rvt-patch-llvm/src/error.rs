@@ -0,0 +1,147 @@
+////////////////////////////////////////////////////////////////
+// Errors
+//
+// Each error belongs to a category with its own exit code so that
+// scripts can distinguish mistakes in how the tool was used (e.g.,
+// selecting a test that does not exist) from problems with the
+// input or output files and from bugs in the tool.
+////////////////////////////////////////////////////////////////
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Categories of error (and their exit codes)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// The command line or a configuration file is wrong
+    Usage = 1,
+    /// The input file cannot be read or is not what we expected
+    Input = 2,
+    /// The output file cannot be written
+    Output = 3,
+    /// A bug in rvt-patch-llvm
+    Internal = 4,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Unable to read a file
+    Read { path: PathBuf, message: String },
+    /// The input file is not LLVM IR or bitcode
+    Parse { path: PathBuf, message: String },
+    /// Unable to write a file
+    Write { path: PathBuf, message: String },
+    /// The stub configuration file is not valid
+    Config { path: PathBuf, message: String },
+    /// No function matches a selector (e.g., 'main')
+    MissingFunction(String),
+    /// More than one function matches a selector
+    /// (the matches are listed as 'path (symbol)')
+    Ambiguous { selector: String, matches: Vec<String> },
+    /// There are no tests to add to the harness
+    NoTests,
+    /// An initializer that we do not understand
+    MalformedInitializer { name: String, reason: String },
+    /// A symbol name that is not valid UTF-8
+    InvalidName(String),
+    /// An LLVM operation failed unexpectedly
+    Llvm(String),
+    /// Anything else that should not happen
+    Internal(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn category(&self) -> Category {
+        match self {
+            Error::Config { .. } | Error::MissingFunction(_) | Error::Ambiguous { .. } | Error::NoTests => {
+                Category::Usage
+            }
+            Error::Read { .. } | Error::Parse { .. } | Error::MalformedInitializer { .. } | Error::InvalidName(_) => {
+                Category::Input
+            }
+            Error::Write { .. } => Category::Output,
+            Error::Llvm(_) | Error::Internal(_) => Category::Internal,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.category() as i32
+    }
+
+    pub fn read(path: &Path, message: impl ToString) -> Error {
+        Error::Read {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn write(path: &Path, message: impl ToString) -> Error {
+        Error::Write {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Read { path, message } => write!(f, "failed to read '{}': {}", path.display(), message),
+            Error::Parse { path, message } => {
+                write!(f, "failed to parse LLVM module '{}': {}", path.display(), message)
+            }
+            Error::Write { path, message } => write!(f, "failed to write '{}': {}", path.display(), message),
+            Error::Config { path, message } => {
+                write!(f, "invalid stub configuration '{}': {}", path.display(), message)
+            }
+            Error::MissingFunction(selector) => write!(f, "unable to find function '{}'", selector),
+            Error::Ambiguous { selector, matches } => {
+                write!(f, "'{}' is ambiguous. It matches", selector)?;
+                for m in matches {
+                    write!(f, "\n    {}", m)?;
+                }
+                Ok(())
+            }
+            Error::NoTests => write!(f, "no tests to add to the harness"),
+            Error::MalformedInitializer { name, reason } => {
+                write!(f, "malformed initializer '{}': {}", name, reason)
+            }
+            Error::InvalidName(name) => write!(f, "symbol name is not valid UTF-8: '{}'", name),
+            Error::Llvm(message) => write!(f, "LLVM operation failed: {}", message),
+            Error::Internal(message) => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<inkwell::builder::BuilderError> for Error {
+    fn from(e: inkwell::builder::BuilderError) -> Error {
+        Error::Llvm(e.to_string())
+    }
+}
+
+impl From<regex::Error> for Error {
+    fn from(e: regex::Error) -> Error {
+        Error::Internal(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Internal(e.to_string())
+    }
+}
+
+/// The name of a symbol (which must be valid UTF-8)
+pub fn symbol_name(name: &std::ffi::CStr) -> Result<String> {
+    name.to_str()
+        .map(|s| s.to_string())
+        .map_err(|_| Error::InvalidName(name.to_string_lossy().into_owned()))
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
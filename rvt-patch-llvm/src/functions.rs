@@ -0,0 +1,108 @@
+////////////////////////////////////////////////////////////////
+// Finding functions
+//
+// Functions are selected either by a regex on their symbol name
+// or by their demangled path (see the 'demangle' module).
+////////////////////////////////////////////////////////////////
+
+use inkwell::module::Module;
+use inkwell::values::FunctionValue;
+use regex::Regex;
+
+use crate::demangle::{demangled_path, path_matches};
+use crate::error::{symbol_name, Error, Result};
+
+/// Find the function selected by 'selector' (see `path_matches`).
+///
+/// It is an error if more than one function is selected.
+pub fn find_function<'ctx>(module: &Module<'ctx>, selector: &str) -> Result<Option<FunctionValue<'ctx>>> {
+    let funs = get_functions_with_path(module, |path| path_matches(path, selector))?;
+    if funs.len() > 1 {
+        return Err(Error::Ambiguous {
+            selector: selector.to_string(),
+            matches: funs
+                .iter()
+                .map(|(path, fun)| format!("{} ({})", path, fun.get_name().to_string_lossy()))
+                .collect(),
+        });
+    }
+    Ok(funs.into_iter().next().map(|(_, fun)| fun))
+}
+
+/// Find all functions whose name matches 're'
+pub fn get_functions<'ctx>(module: &Module<'ctx>, re: &Regex) -> Result<Vec<FunctionValue<'ctx>>> {
+    let mut funs = Vec::new();
+    let mut op_fun = module.get_first_function();
+    while let Some(fun) = op_fun {
+        if re.is_match(&symbol_name(fun.get_name())?) {
+            funs.push(fun);
+        }
+        op_fun = fun.get_next_function();
+    }
+    Ok(funs)
+}
+
+/// Find all Rust functions whose demangled path satisfies 'pred'
+/// (returning the path of each function too).
+pub fn get_functions_with_path<'ctx>(
+    module: &Module<'ctx>,
+    pred: impl Fn(&str) -> bool,
+) -> Result<Vec<(String, FunctionValue<'ctx>)>> {
+    let mut funs = Vec::new();
+    let mut op_fun = module.get_first_function();
+    while let Some(fun) = op_fun {
+        let name = symbol_name(fun.get_name())?;
+        if let Some(path) = demangled_path(&name) {
+            if pred(&path) {
+                funs.push((path, fun));
+            }
+        }
+        op_fun = fun.get_next_function();
+    }
+    Ok(funs)
+}
+
+/// Convert a path pattern into a regex.
+///
+/// '*' matches any part of a path segment and '**' matches anything.
+pub fn path_pattern(pattern: &str) -> Result<Regex> {
+    let re = regex::escape(pattern)
+        .replace(r"\*\*", ".*")
+        .replace(r"\*", "[^:]*");
+    Ok(Regex::new(&format!("^{}$", re))?)
+}
+
+/// Find all functions whose demangled path matches 're'
+pub fn get_functions_by_path<'ctx>(module: &Module<'ctx>, re: &Regex) -> Result<Vec<FunctionValue<'ctx>>> {
+    Ok(get_functions_with_path(module, |path| re.is_match(path))?
+        .into_iter()
+        .map(|(_, fun)| fun)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        let re = path_pattern("mylib::*answer").unwrap();
+        assert!(re.is_match("mylib::answer"));
+        assert!(re.is_match("mylib::stub_answer"));
+        assert!(!re.is_match("mylib::inner::answer"));
+        assert!(!re.is_match("other::mylib::answer"));
+
+        let re = path_pattern("std::**::print").unwrap();
+        assert!(re.is_match("std::io::stdio::print"));
+        assert!(!re.is_match("std::println"));
+
+        // Other characters are not special
+        let re = path_pattern("<a::S as a::Tr>::f").unwrap();
+        assert!(re.is_match("<a::S as a::Tr>::f"));
+        assert!(!re.is_match("a::f"));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
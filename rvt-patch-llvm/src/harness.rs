@@ -0,0 +1,145 @@
+////////////////////////////////////////////////////////////////
+// Test harnesses
+//
+// A harness replaces the 'main' function that rustc generates for
+// tests with one that runs a single test chosen by a symbolic value
+// so that one verifier run checks several tests.
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::module::Module;
+use log::{info, warn};
+
+use crate::demangle::path_matches;
+use crate::entries;
+use crate::error::{Error, Result};
+use crate::llvm;
+use crate::nondet::build_nondet_int;
+use crate::pass::{Output, Pass};
+
+/// Replace 'main' with a harness that runs the tests selected by
+/// 'selectors' (or all tests if 'selectors' is empty).
+///
+/// The symbolic value is created using '__VERIFIER_nondet_u32' if
+/// 'seahorn' is set and using 'klee_make_symbolic' otherwise.
+/// 'tests' is set to the paths of the tests in the harness
+/// (the test with path 'tests[i]' runs when the symbolic value is 'i').
+pub struct HarnessPass {
+    pub selectors: Vec<String>,
+    pub seahorn: bool,
+    pub tests: Output<Vec<String>>,
+}
+
+impl HarnessPass {
+    pub fn new(selectors: Vec<String>, seahorn: bool) -> HarnessPass {
+        HarnessPass {
+            selectors,
+            seahorn,
+            tests: Output::default(),
+        }
+    }
+}
+
+impl Pass for HarnessPass {
+    fn name(&self) -> &str {
+        "harness"
+    }
+
+    fn run<'ctx>(&self, context: &'ctx Context, module: &Module<'ctx>) -> Result<()> {
+        let tests = handle_harness(context, module, &self.selectors, self.seahorn)?;
+        self.tests.set(tests);
+        Ok(())
+    }
+}
+
+/// Replace 'main' with a harness that runs one of the tests selected by
+/// 'selectors' (or all tests if 'selectors' is empty).
+///
+/// The test is chosen using a symbolic value so that verifying the harness
+/// verifies every selected test. Returns the paths of the tests in the
+/// order of their selector values.
+///
+///     define i32 @main(i32 %0, i8** %1) {
+///     entry:
+///       %selector = ... symbolic i32 ...
+///       switch i32 %selector, label %done [ i32 0, label %test0
+///                                           i32 1, label %test1 ]
+///     test0:
+///       call void @t0()
+///       br label %done
+///     test1:
+///       call void @t1()
+///       br label %done
+///     done:
+///       ret i32 0
+///     }
+///
+fn handle_harness<'a>(
+    context: &'a Context,
+    module: &Module<'a>,
+    selectors: &[String],
+    seahorn: bool,
+) -> Result<Vec<String>> {
+    let mut tests = Vec::new();
+    for entry in entries::find_entries(module)? {
+        if !selectors.is_empty() && !selectors.iter().any(|s| path_matches(&entry.path, s)) {
+            continue;
+        }
+        match entry.symbol.as_ref().and_then(|s| module.get_function(s)) {
+            Some(f) if f.count_params() == 0 && f.get_type().get_return_type().is_none() => {
+                tests.push((entry.path, f))
+            }
+            _ => warn!(
+                "Not adding '{}' to the harness because it was inlined or does not have type 'fn()'.",
+                entry.path
+            ),
+        }
+    }
+    for s in selectors {
+        if !tests.iter().any(|(path, _)| path_matches(path, s)) {
+            warn!("No test matches '{}'.", s);
+        }
+    }
+    if tests.is_empty() {
+        return Err(Error::NoTests);
+    }
+
+    // Remove the main function rustc generates (that runs the test harness).
+    if let Some(main) = module.get_function("main") {
+        unsafe {
+            // Any use of `main` after calling `delete` is unsafe
+            main.delete();
+        }
+        info!("Deleted 'main' (was added by rustc).");
+    }
+
+    let i32_type = context.i32_type();
+    let ppi8_type = llvm::i8_ptr_ptr_type(context);
+    let main_type = i32_type.fn_type(&[i32_type.into(), ppi8_type.into()], false);
+    let main = module.add_function("main", main_type, None);
+    let entry = context.append_basic_block(main, "entry");
+    let done = context.append_basic_block(main, "done");
+    let builder = context.create_builder();
+
+    let mut cases = Vec::new();
+    for (i, (_, f)) in tests.iter().enumerate() {
+        let bb = context.append_basic_block(main, &format!("test{}", i));
+        builder.position_at_end(bb);
+        builder.build_call(*f, &[], "")?;
+        builder.build_unconditional_branch(done)?;
+        cases.push((i32_type.const_int(i as u64, false), bb));
+    }
+
+    builder.position_at_end(entry);
+    let selector = build_nondet_int(context, module, &builder, i32_type, "selector", seahorn)?;
+    builder.build_switch(selector, done, &cases)?;
+
+    builder.position_at_end(done);
+    builder.build_return(Some(&i32_type.const_zero()))?;
+    info!("Built harness 'main' for {} tests.", tests.len());
+    Ok(tests.into_iter().map(|(path, _)| path).collect())
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,333 @@
+////////////////////////////////////////////////////////////////
+// Calling initializers
+//
+// Verifiers start execution at an entry point like 'main' and do
+// not run the initializers that the C runtime runs before 'main'.
+// Initializers are found in two places
+//
+// - globals in the sections '.init_array' and '.init_array.NNNNN'
+//   (e.g., std uses this to record argc and argv)
+// - the 'llvm.global_ctors' list of (priority, function, data) entries
+//
+// We combine all initializers into a single function that calls them
+// in priority order (lowest first, with initializers that have no
+// priority last, as on ELF platforms) and insert a call to that
+// function at the start of each entry point.
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::types::{BasicMetadataTypeEnum, BasicTypeEnum};
+use inkwell::values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, FunctionValue, GlobalValue};
+use log::{info, warn};
+
+use crate::error::{Error, Result};
+use crate::functions::find_function;
+use crate::llvm;
+use crate::pass::Pass;
+
+/// Name of the function that calls all the initializers
+const INIT_FUNCTION: &str = "__init_function";
+
+/// Priority of initializers that do not have a priority
+const DEFAULT_PRIORITY: u64 = 65535;
+
+/// An initializer function and its priority
+struct Initializer<'a> {
+    priority: u64,
+    function: FunctionValue<'a>,
+}
+
+/// Call all the initializers at the start of each entry point.
+///
+/// Entry points are symbol names or paths (see `path_matches`).
+pub struct InitializersPass {
+    pub entries: Vec<String>,
+}
+
+impl InitializersPass {
+    pub fn new(entries: Vec<String>) -> InitializersPass {
+        InitializersPass { entries }
+    }
+}
+
+impl Default for InitializersPass {
+    /// Call the initializers from 'main'
+    fn default() -> InitializersPass {
+        InitializersPass::new(vec!["main".to_string()])
+    }
+}
+
+impl Pass for InitializersPass {
+    fn name(&self) -> &str {
+        "initializers"
+    }
+
+    fn run<'ctx>(&self, context: &'ctx Context, module: &Module<'ctx>) -> Result<()> {
+        handle_initializers(context, module, &self.entries)
+    }
+}
+
+fn handle_initializers<'a>(context: &'a Context, module: &Module<'a>, entries: &[String]) -> Result<()> {
+    let mut entry_points = Vec::new();
+    for selector in entries {
+        let entry = match module.get_function(selector) {
+            Some(f) => Some(f),
+            None => find_function(module, selector)?,
+        };
+        match entry {
+            Some(entry) if entry.get_first_basic_block().is_some() => entry_points.push(entry),
+            Some(_) => warn!("Entry point '{}' has no body", selector),
+            None => return Err(Error::MissingFunction(selector.to_string())),
+        }
+    }
+
+    let initializers = collect_initializers(module)?;
+    if initializers.is_empty() {
+        info!("No initializers to handle");
+        return Ok(());
+    }
+
+    let init = build_fanout(context, module, INIT_FUNCTION, &initializers)?;
+    info!(
+        "Combined {} initializers into '{}'",
+        initializers.len(),
+        INIT_FUNCTION
+    );
+
+    for entry in entry_points {
+        insert_init_call(context, init, entry)?;
+        info!(
+            "Inserted call to '{}' into '{}'",
+            INIT_FUNCTION,
+            entry.get_name().to_string_lossy()
+        )
+    }
+    Ok(())
+}
+
+/// Find all the initializers in a module (sorted by priority).
+///
+/// 'llvm.global_ctors' is deleted so that tools that call the
+/// functions it lists (e.g., KLEE) do not call them a second time.
+fn collect_initializers<'a>(module: &Module<'a>) -> Result<Vec<Initializer<'a>>> {
+    let mut initializers = Vec::new();
+
+    let mut og = module.get_first_global();
+    while let Some(g) = og {
+        let priority = g
+            .get_section()
+            .and_then(|s| s.to_str().ok())
+            .and_then(section_priority);
+        if let (Some(priority), Some(value)) = (priority, g.get_initializer()) {
+            let name = g.get_name().to_string_lossy();
+            info!("Found initializer {:?}", name);
+            let mut functions = Vec::new();
+            referenced_functions(module, &name, value, &mut functions)?;
+            if functions.is_empty() {
+                return Err(Error::MalformedInitializer {
+                    name: name.into_owned(),
+                    reason: "it does not contain a function pointer".to_string(),
+                });
+            }
+            initializers.extend(functions.into_iter().map(|function| Initializer { priority, function }));
+        }
+        og = g.get_next_global();
+    }
+
+    if let Some(ctors) = module.get_global("llvm.global_ctors") {
+        initializers.extend(global_ctors(module, ctors)?);
+        unsafe {
+            // Any use of `ctors` after calling `delete` is unsafe
+            ctors.delete();
+        }
+        info!("Deleted 'llvm.global_ctors'");
+    }
+
+    // (The sort is stable so initializers with the same priority are
+    // called in the order that they appear in the module.)
+    initializers.sort_by_key(|i| i.priority);
+    Ok(initializers)
+}
+
+/// The priority of initializers in section 's' (if it is an initializer section)
+fn section_priority(s: &str) -> Option<u64> {
+    if s == ".init_array" {
+        Some(DEFAULT_PRIORITY)
+    } else {
+        s.strip_prefix(".init_array.")?.parse().ok()
+    }
+}
+
+/// The initializers listed in 'llvm.global_ctors'
+///
+///     @llvm.global_ctors = appending global [1 x { i32, void ()*, i8* }]
+///         [{ i32, void ()*, i8* } { i32 65535, void ()* @ctor, i8* null }]
+///
+/// (The 'data' field is ignored and older modules do not have it.)
+fn global_ctors<'a>(module: &Module<'a>, ctors: GlobalValue<'a>) -> Result<Vec<Initializer<'a>>> {
+    const NAME: &str = "llvm.global_ctors";
+    let mut initializers = Vec::new();
+    let list = match ctors.get_initializer() {
+        Some(list) => list,
+        None => return Ok(initializers),
+    };
+    for ctor in llvm::operands(list) {
+        let fields = llvm::operands(ctor);
+        if fields.len() < 2 {
+            return Err(Error::MalformedInitializer {
+                name: NAME.to_string(),
+                reason: "expected entries of the form { priority, function, data }".to_string(),
+            });
+        }
+        let priority = fields
+            .first()
+            .filter(|p| p.is_int_value())
+            .and_then(|p| p.into_int_value().get_zero_extended_constant())
+            .unwrap_or(DEFAULT_PRIORITY);
+        let mut functions = Vec::new();
+        // (Null function pointers are ignored.)
+        referenced_functions(module, NAME, fields[1], &mut functions)?;
+        initializers.extend(functions.into_iter().map(|function| Initializer { priority, function }));
+    }
+    Ok(initializers)
+}
+
+/// Collect the functions that a constant refers to.
+///
+/// Initializer sections contain function pointers that are usually cast
+/// to some other type and may be wrapped in a struct or an array
+/// (e.g., rustc generates '<{ i8*, [0 x i8] }>').
+/// 'owner' is the name of the global that contains the constant.
+fn referenced_functions<'a>(
+    module: &Module<'a>,
+    owner: &str,
+    v: BasicValueEnum<'a>,
+    functions: &mut Vec<FunctionValue<'a>>,
+) -> Result<()> {
+    if v.is_pointer_value() {
+        let p = v.into_pointer_value();
+        let name = p.get_name().to_string_lossy();
+        if !name.is_empty() {
+            return match module.get_function(&name) {
+                Some(f) => {
+                    functions.push(f);
+                    Ok(())
+                }
+                None => Err(Error::MalformedInitializer {
+                    name: owner.to_string(),
+                    reason: format!("'{}' is not a function", name),
+                }),
+            };
+        }
+    }
+    // Constant expressions (e.g., bitcasts) and aggregates
+    for op in llvm::operands(v) {
+        referenced_functions(module, owner, op, functions)?;
+    }
+    Ok(())
+}
+
+/// The arguments (argc, argv, envp) that are passed to initializers
+/// when the entry point does not provide them.
+fn default_args(context: &Context) -> Vec<BasicValueEnum<'_>> {
+    let ppi8_type = llvm::i8_ptr_ptr_type(context);
+    vec![
+        context.i32_type().const_zero().as_basic_value_enum(),
+        ppi8_type.const_null().as_basic_value_enum(),
+        ppi8_type.const_null().as_basic_value_enum(),
+    ]
+}
+
+/// Build a function that calls each initializer in order
+///
+///     define void @__init_function(i32 %0, i8** %1, i8** %2) {
+///     entry:
+///       call void @f1(i32 %0, i8** %1, i8** %2)
+///       call void @f2()
+///       ret void
+///     }
+///
+/// Each initializer is passed as many of the arguments (argc, argv, envp)
+/// as it takes. Initializers that take other arguments are skipped.
+fn build_fanout<'a>(
+    context: &'a Context,
+    module: &Module<'a>,
+    nm: &str,
+    initializers: &[Initializer<'a>],
+) -> Result<FunctionValue<'a>> {
+    let param_types: Vec<BasicTypeEnum> = default_args(context).iter().map(|a| a.get_type()).collect();
+    let metadata_types: Vec<BasicMetadataTypeEnum> = param_types.iter().map(|t| (*t).into()).collect();
+    let ty = context.void_type().fn_type(&metadata_types, false);
+    let function = module.add_function(nm, ty, None);
+    let args = metadata_args(function.get_params());
+    let basic_block = context.append_basic_block(function, "entry");
+    let builder = context.create_builder();
+    builder.position_at_end(basic_block);
+
+    for i in initializers {
+        let name = i.function.get_name().to_string_lossy();
+        let types: Vec<BasicTypeEnum> = i.function.get_params().iter().map(|p| p.get_type()).collect();
+        if types.len() <= param_types.len() && types[..] == param_types[..types.len()] {
+            info!("Calling initializer '{}' (priority {})", name, i.priority);
+            builder.build_call(i.function, &args[..types.len()], "")?;
+        } else {
+            warn!(
+                "Skipping initializer '{}' because it has type {}",
+                name,
+                i.function.get_type().print_to_string().to_string_lossy()
+            );
+        }
+    }
+    builder.build_return(None)?;
+
+    Ok(function)
+}
+
+/// Insert a call to 'init' at the start of 'entry'
+///
+/// Entry points that take (argc, argv, envp) (or a prefix of them)
+/// pass them to the initializers. Zero and null pointers are passed
+/// for any other arguments.
+fn insert_init_call<'a>(context: &'a Context, init: FunctionValue<'a>, entry: FunctionValue<'a>) -> Result<()> {
+    let params = entry.get_params();
+    let args: Vec<_> = default_args(context)
+        .into_iter()
+        .enumerate()
+        .map(|(i, default)| match params.get(i) {
+            Some(p) if p.get_type() == default.get_type() => *p,
+            _ => default,
+        })
+        .collect();
+    insert_call_at_head(context, init, args, entry)
+}
+
+/// Convert values to the type used for call arguments
+fn metadata_args(args: Vec<BasicValueEnum>) -> Vec<BasicMetadataValueEnum> {
+    args.into_iter().map(|a| a.into()).collect()
+}
+
+fn insert_call_at_head<'a>(
+    context: &Context,
+    f: FunctionValue<'a>,
+    args: Vec<BasicValueEnum<'a>>,
+    insertee: FunctionValue<'a>,
+) -> Result<()> {
+    let first_instruction = insertee
+        .get_first_basic_block()
+        .and_then(|bb| bb.get_first_instruction())
+        .ok_or_else(|| {
+            Error::Llvm(format!(
+                "unable to find where to insert a call into '{}'",
+                insertee.get_name().to_string_lossy()
+            ))
+        })?;
+    let builder = context.create_builder();
+    builder.position_before(&first_instruction);
+    builder.build_call(f, &metadata_args(args), "")?;
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,78 @@
+////////////////////////////////////////////////////////////////
+// Transformations of rustc generated LLVM modules
+//
+// Each transformation is a `Pass` and a `Pipeline` runs a sequence of
+// passes. The rvt-patch-llvm binary builds a pipeline from its command
+// line but other tools can use the passes directly, e.g.,
+//
+//     let context = Context::create();
+//     let module = read_module(&context, Path::new("input.bc"))?;
+//     let mut pipeline = Pipeline::new();
+//     pipeline
+//         .add(InitializersPass::default())
+//         .add(StubPass::Attributes);
+//     pipeline.run(&context, &module)?;
+//     write_module(&module, Path::new("output.bc"))?;
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::memory_buffer::MemoryBuffer;
+use inkwell::module::Module;
+use log::info;
+use std::ffi::OsStr;
+use std::path::Path;
+
+mod demangle;
+mod entries;
+mod error;
+mod functions;
+mod harness;
+mod initializers;
+mod llvm;
+mod nondet;
+mod panic;
+mod pass;
+mod seahorn;
+mod stubs;
+
+pub use demangle::{demangled_path, path_matches};
+pub use entries::{find_entries, write_expectations, Entry, ShouldPanic};
+pub use error::{Category, Error, Result};
+pub use harness::HarnessPass;
+pub use initializers::InitializersPass;
+pub use panic::PanicRedirectPass;
+pub use pass::{Output, Pass, Pipeline};
+pub use seahorn::{seahorn_stub_rules, SeahornMainPass};
+pub use stubs::{read_stub_config, StubAction, StubConfig, StubPass, StubRule};
+
+/// Read an LLVM module (either LLVM IR or bitcode)
+pub fn read_module<'ctx>(context: &'ctx Context, path: &Path) -> Result<Module<'ctx>> {
+    info!("Reading input from {}", path.display());
+    let memory_buffer = MemoryBuffer::create_from_file(path).map_err(|e| Error::read(path, e))?;
+    context
+        .create_module_from_ir(memory_buffer)
+        .map_err(|e| Error::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+}
+
+/// Write an LLVM module
+/// (as bitcode if the extension is '.bc' and as LLVM IR otherwise)
+pub fn write_module(module: &Module, path: &Path) -> Result<()> {
+    info!("Writing output to {}", path.display());
+    if path.extension() == Some(OsStr::new("bc")) {
+        // output bitcode
+        if !module.write_bitcode_to_path(path) {
+            return Err(Error::write(path, "LLVM failed to write bitcode"));
+        }
+    } else {
+        // output disassembled bitcode
+        module.print_to_file(path).map_err(|e| Error::write(path, e))?;
+    }
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,145 @@
+////////////////////////////////////////////////////////////////
+// Portability across LLVM versions
+//
+// The version of LLVM is selected using a cargo feature
+// (e.g., 'llvm15-0'). Most of the inkwell API is the same for all
+// versions but LLVM 15 replaced typed pointers (e.g., 'i8**')
+// with opaque pointers ('ptr') so the transformations use the
+// functions in this module for anything that involves pointer
+// types and for the parts of the LLVM API that inkwell does not wrap.
+////////////////////////////////////////////////////////////////
+
+use inkwell::builder::{Builder, BuilderError};
+use inkwell::context::Context;
+use inkwell::types::{BasicType, PointerType};
+use inkwell::values::{AsValueRef, BasicValueEnum, CallSiteValue, FunctionValue, PointerValue};
+use inkwell::AddressSpace;
+
+use crate::error::Error;
+
+#[cfg(feature = "llvm10-0")]
+use llvm_sys_100 as llvm_sys;
+#[cfg(feature = "llvm11-0")]
+use llvm_sys_110 as llvm_sys;
+#[cfg(feature = "llvm12-0")]
+use llvm_sys_120 as llvm_sys;
+#[cfg(feature = "llvm13-0")]
+use llvm_sys_130 as llvm_sys;
+#[cfg(feature = "llvm14-0")]
+use llvm_sys_140 as llvm_sys;
+#[cfg(feature = "llvm15-0")]
+use llvm_sys_150 as llvm_sys;
+#[cfg(feature = "llvm16-0")]
+use llvm_sys_160 as llvm_sys;
+#[cfg(feature = "llvm17-0")]
+use llvm_sys_170 as llvm_sys;
+#[cfg(feature = "llvm18-0")]
+use llvm_sys_180 as llvm_sys;
+
+#[cfg(not(any(
+    feature = "llvm10-0",
+    feature = "llvm11-0",
+    feature = "llvm12-0",
+    feature = "llvm13-0",
+    feature = "llvm14-0",
+    feature = "llvm15-0",
+    feature = "llvm16-0",
+    feature = "llvm17-0",
+    feature = "llvm18-0"
+)))]
+compile_error!("Select the version of LLVM using a feature like 'llvm10-0'.");
+
+use llvm_sys::core::{LLVMGetNumOperands, LLVMGetOperand, LLVMSetPersonalityFn};
+
+pub use pointers::{build_load, i8_ptr_ptr_type, i8_ptr_type};
+
+/// Typed pointers (LLVM 10-14)
+#[cfg(not(any(
+    feature = "llvm15-0",
+    feature = "llvm16-0",
+    feature = "llvm17-0",
+    feature = "llvm18-0"
+)))]
+mod pointers {
+    use super::*;
+
+    /// The type 'i8*'
+    pub fn i8_ptr_type(context: &Context) -> PointerType<'_> {
+        context.i8_type().ptr_type(AddressSpace::default())
+    }
+
+    /// The type 'i8**'
+    pub fn i8_ptr_ptr_type(context: &Context) -> PointerType<'_> {
+        i8_ptr_type(context).ptr_type(AddressSpace::default())
+    }
+
+    /// Load a value of type 'ty' from 'ptr'
+    pub fn build_load<'a>(
+        builder: &Builder<'a>,
+        _ty: impl BasicType<'a>,
+        ptr: PointerValue<'a>,
+        name: &str,
+    ) -> Result<BasicValueEnum<'a>, BuilderError> {
+        builder.build_load(ptr, name)
+    }
+}
+
+/// Opaque pointers (LLVM 15 onwards)
+#[cfg(any(
+    feature = "llvm15-0",
+    feature = "llvm16-0",
+    feature = "llvm17-0",
+    feature = "llvm18-0"
+))]
+mod pointers {
+    use super::*;
+
+    /// The type 'ptr' (replaces 'i8*')
+    pub fn i8_ptr_type(context: &Context) -> PointerType<'_> {
+        context.ptr_type(AddressSpace::default())
+    }
+
+    /// The type 'ptr' (replaces 'i8**')
+    pub fn i8_ptr_ptr_type(context: &Context) -> PointerType<'_> {
+        context.ptr_type(AddressSpace::default())
+    }
+
+    /// Load a value of type 'ty' from 'ptr'
+    pub fn build_load<'a>(
+        builder: &Builder<'a>,
+        ty: impl BasicType<'a>,
+        ptr: PointerValue<'a>,
+        name: &str,
+    ) -> Result<BasicValueEnum<'a>, BuilderError> {
+        builder.build_load(ty, ptr, name)
+    }
+}
+
+/// The operands of a constant
+/// (e.g., the fields of a struct or the value that a bitcast converts).
+pub fn operands(v: BasicValueEnum) -> Vec<BasicValueEnum> {
+    let v = v.as_value_ref();
+    unsafe {
+        let n = LLVMGetNumOperands(v);
+        (0..n as u32)
+            .map(|i| BasicValueEnum::new(LLVMGetOperand(v, i)))
+            .collect()
+    }
+}
+
+/// Remove the personality function of a function
+/// (which must be done when deleting the body of a function).
+pub fn remove_personality_function(fun: FunctionValue) {
+    unsafe { LLVMSetPersonalityFn(fun.as_value_ref(), std::ptr::null_mut()) }
+}
+
+/// The value returned by a call
+pub fn call_result(call: CallSiteValue) -> Result<BasicValueEnum, Error> {
+    call.try_as_basic_value()
+        .left()
+        .ok_or_else(|| Error::Llvm("call does not return a value".to_string()))
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -1,23 +1,20 @@
 use log::info;
-use regex::Regex;
-use std::ffi::OsStr;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
 use inkwell::context::Context;
-use inkwell::memory_buffer::MemoryBuffer;
-use inkwell::module::Linkage;
-use inkwell::module::Module;
-use inkwell::types::{AnyType, FunctionType};
-use inkwell::values::{AnyValue, BasicValue, BasicValueEnum};
-use inkwell::values::{FunctionValue, GlobalValue, PointerValue};
-use inkwell::AddressSpace;
+
+use rvt_patch_llvm::{
+    find_entries, read_module, read_stub_config, seahorn_stub_rules, write_expectations, write_module, Error,
+    HarnessPass, InitializersPass, Output, PanicRedirectPass, Pipeline, Result, SeahornMainPass, StubPass,
+};
 
 // Command line argument parsing
 #[derive(StructOpt)]
 #[structopt(
     name = "rvt-patch-llvm",
     about = "Preprocess rustc generated llvm code for verification.",
+    after_help = "EXIT CODES:\n    1  usage error\n    2  invalid input\n    3  unable to write output\n    4  internal error",
     // version number is taken automatically from Cargo.toml
 )]
 struct Opt {
@@ -35,18 +32,57 @@ struct Opt {
     )]
     output: PathBuf,
 
-    /// Call initializers from main
+    /// Call initializers from main (or from the functions selected by --init-entry)
     #[structopt(short, long)]
     initializers: bool,
 
+    /// Select a function to call initializers from (instead of 'main').
+    /// This can be used more than once.
+    #[structopt(long, name = "ENTRY", number_of_values = 1)]
+    init_entry: Vec<String>,
+
     /// SeaHorn preparation (conflicts with --initializers)
     #[structopt(short, long, conflicts_with = "initializers")]
     seahorn: bool,
 
-    /// Select a specific test to run (instead of 'main')
+    /// Redirect calls to functions that have stubs (see `#[verifier::stub]`)
+    #[structopt(long)]
+    stub: bool,
+
+    /// Stub configuration file (TOML) listing functions to replace
+    #[structopt(long, name = "STUBS", parse(from_os_str))]
+    stub_config: Option<PathBuf>,
+
+    /// Redirect panics and aborts to the verifier hook SYMBOL
+    /// (a function of type 'fn(Option<&PanicInfo>) -> !').
+    /// SeaHorn preparation uses 'spanic' (if present) by default.
+    #[structopt(long, name = "SYMBOL")]
+    panic_handler: Option<String>,
+
+    /// Select a specific test to run (instead of 'main').
+    /// This is a path like 'mycrate::tests::t1' or a suffix of a path like 'tests::t1'.
     #[structopt(short, long, name = "TEST")]
     test: Option<String>,
 
+    /// Replace 'main' with a harness that runs one of the tests
+    /// (chosen using a symbolic value). All tests are included
+    /// unless some are selected by --harness-test.
+    #[structopt(long)]
+    harness: bool,
+
+    /// Select a test to add to the harness (see --test for the format).
+    /// This can be used more than once.
+    #[structopt(long, name = "HARNESS_TEST", number_of_values = 1, requires = "harness")]
+    harness_test: Vec<String>,
+
+    /// List the #[test] functions (as JSON) instead of writing an output file
+    #[structopt(long)]
+    list_entries: bool,
+
+    /// Write the expected failures of #[should_panic] tests to a JSON file
+    #[structopt(long, name = "EXPECTATIONS", parse(from_os_str))]
+    expectations: Option<PathBuf>,
+
     /// Increase message verbosity
     #[structopt(short, long, parse(from_occurrences))]
     verbosity: usize,
@@ -55,273 +91,115 @@ struct Opt {
 fn main() {
     let opt = Opt::from_args();
 
+    if let Err(e) = run(&opt) {
+        eprintln!("ERROR: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run(opt: &Opt) -> Result<()> {
     #[rustfmt::skip]
     stderrlog::new()
         .verbosity(opt.verbosity)
         .init()
-        .unwrap();
-
-    // Read the input file
-    info!("Reading input from {}", opt.input.to_str().unwrap());
-    let memory_buffer =
-        MemoryBuffer::create_from_file(&opt.input).expect("ERROR: failed to open file.");
+        .map_err(|e| Error::Internal(e.to_string()))?;
 
     let context = Context::create();
-    let mut module = context
-        .create_module_from_ir(memory_buffer)
-        .expect("ERROR: failed to create module.");
-
-    if opt.initializers {
-        handle_initializers(&context, &mut module);
-    }
+    let module = read_module(&context, &opt.input)?;
 
-    if opt.seahorn {
-        handle_main(&module, &opt.test);
-
-        handle_panic(&module);
-
-        replace_def_with_dec(
-            &module,
-            &Regex::new(r"^_ZN3std2io5stdio7_eprint17h[a-f0-9]{16}E$").unwrap(),
-        );
-        replace_def_with_dec(
-            &module,
-            &Regex::new(r"^_ZN3std2io5stdio6_print17h[a-f0-9]{16}E$").unwrap(),
-        );
+    if opt.list_entries {
+        let entries = find_entries(&module)?;
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
     }
 
-    // Write output file
-    info!("Writing output to {}", opt.output.to_str().unwrap());
-    if opt.output.extension() == Some(OsStr::new("bc")) {
-        // output bitcode
-        // TODO: this function returns bool but the doc doesn't say anything about it.
-        module.write_bitcode_to_path(&opt.output);
-    } else {
-        // output disassembled bitcode
-        module
-            .print_to_file(&opt.output)
-            .expect("ERROR: failed to write to file.");
+    if let Some(path) = &opt.expectations {
+        write_expectations(&module, path)?;
     }
-}
 
-////////////////////////////////////////////////////////////////
-// Transformations associated with initializers
-////////////////////////////////////////////////////////////////
+    let (pipeline, outputs) = build_pipeline(opt)?;
+    info!("Passes: {}", pipeline.names().join(", "));
+    pipeline.run(&context, &module)?;
 
-fn handle_initializers(context: &Context, module: &mut Module) {
-    if let Some(initializer) =
-        collect_initializers(context, module, ".init_array", "__init_function")
-    {
-        info!(
-            "Combined .init_array* initializers into '{}'",
-            initializer.get_name().to_str().unwrap()
-        );
-
-        let main = module
-            .get_function("main")
-            .expect("Unable to find 'main' function");
-        let mut args = get_fn_args(main);
-        assert!(args.len() == 2); // We expect "i32 @main(i32 %0, i8** %1)"
-        let i8_type = context.i8_type();
-        let pi8_type = i8_type.ptr_type(AddressSpace::Generic);
-        let ppi8_type = pi8_type.ptr_type(AddressSpace::Generic);
-        args.push(ppi8_type.const_null().as_basic_value_enum());
-        insert_call_at_head(context, initializer, args, main);
-        info!(
-            "Inserted call to '{}' into 'main'",
-            initializer.get_name().to_str().unwrap()
-        )
-    } else {
-        info!("No initializers to handle")
+    // Let scripts map the harness selector to the test
+    // and find the SeaHorn entry point.
+    for (i, path) in outputs.tests.get().unwrap_or_default().iter().enumerate() {
+        println!("HARNESS: {} {}", i, path);
     }
-}
-
-/// Collect all the initializers in a section (whose name starts with 'prefix')
-/// into a single function that calls all the initializers.
-fn collect_initializers<'a>(
-    context: &Context,
-    module: &mut Module<'a>,
-    prefix: &str,
-    nm: &str,
-) -> Option<FunctionValue<'a>> {
-    let vs = collect_variables_in_section(module, prefix);
-    for v in &vs {
-        info!("Found initializer {:?}", v.get_name().to_str().unwrap());
+    if let Some(entry) = outputs.entry.get() {
+        println!("ENTRY: {}", entry);
     }
 
-    let fps: Vec<PointerValue> = vs.iter().map(get_initializer_function).collect();
+    write_module(&module, &opt.output)
+}
 
-    if !fps.is_empty() {
-        let fp = fps[0];
+/// Results of the passes that are reported on stdout
+#[derive(Default)]
+struct Outputs {
+    tests: Output<Vec<String>>,
+    entry: Output<String>,
+}
 
-        // dereference the pointer type
-        let fp_type = fp.get_type().get_element_type().into_function_type();
-        info!("Initializer type {:?}", fp_type.print_to_string());
+/// The passes selected by the command line (in the order that they must run)
+fn build_pipeline(opt: &Opt) -> Result<(Pipeline, Outputs)> {
+    let mut pipeline = Pipeline::new();
+    let mut outputs = Outputs::default();
 
-        Some(build_fanout(context, module, nm, fp_type, fps))
-    } else {
-        None
+    // (This is done before handling initializers so that they are called by the harness.)
+    if opt.harness {
+        let pass = HarnessPass::new(opt.harness_test.clone(), opt.seahorn);
+        outputs.tests = pass.tests.clone();
+        pipeline.add(pass);
     }
-}
 
-/// Collect variables that are assigned to a section whose name matches prefix
-fn collect_variables_in_section<'a>(module: &Module<'a>, prefix: &str) -> Vec<GlobalValue<'a>> {
-    let mut vs = Vec::new();
-    // todo: should implement an iterator for global values so that the following loop
-    // becomes just iter().filter_map().filter_map().filter()
-    let mut og = module.get_first_global();
-    while let Some(g) = og {
-        if let Some(s) = g.get_section() {
-            if let Ok(s) = s.to_str() {
-                if s.starts_with(prefix) {
-                    vs.push(g)
-                }
-            }
+    if opt.initializers {
+        if opt.init_entry.is_empty() {
+            pipeline.add(InitializersPass::default());
+        } else {
+            pipeline.add(InitializersPass::new(opt.init_entry.clone()));
         }
-        og = g.get_next_global();
     }
-    vs
-}
 
-/// Convert the contents of an initializer section to a function pointer.
-///
-/// Initializer sections contain structs where the first field is a function pointer cast to
-/// some other type.
-fn get_initializer_function<'a>(v: &GlobalValue<'a>) -> PointerValue<'a> {
-    let i = v.get_initializer().unwrap().into_struct_value();
-    assert!(i.get_num_operands() == 2); // expecting two fields in struct
-    let i = i.get_operand(0).unwrap();
-    assert!(i.get_num_operands() == 1); // expecting bitcast
-    let fp = i.get_operand(0).unwrap();
-    fp.into_pointer_value()
-}
-
-/// Given a list of functions of type 'ty', build a function that calls each function in order
-///
-/// Assumes (without checking) that return type is void
-///
-///     define void @fanout(i32 %0, i32 %1) {
-///     entry:
-///       call void @f1(i32 %0, i32 %1)
-///       call void @f2(i32 %0, i32 %1)
-///       call void @f3(i32 %0, i32 %1)
-///       ret void
-///     }
-///
-fn build_fanout<'a>(
-    context: &Context,
-    module: &mut Module<'a>,
-    nm: &str,
-    ty: FunctionType<'a>,
-    fps: Vec<PointerValue<'a>>,
-) -> FunctionValue<'a> {
-    let function = module.add_function(nm, ty, None);
-    let args = get_fn_args(function);
-    let basic_block = context.append_basic_block(function, "entry");
-    let builder = context.create_builder();
-    builder.position_at_end(basic_block);
-
-    for fp in fps {
-        builder.build_call(fp, &args, "");
-        builder.build_return(None);
-        // println!("Built function {:?}", function)
+    if opt.stub {
+        pipeline.add(StubPass::Attributes);
     }
 
-    function
-}
-
-fn get_fn_args<'a>(function: FunctionValue<'a>) -> Vec<BasicValueEnum<'a>> {
-    (0..function.count_params())
-        .map(|i| function.get_nth_param(i).unwrap())
-        .collect()
-}
-
-fn insert_call_at_head<'a>(
-    context: &Context,
-    f: FunctionValue<'a>,
-    args: Vec<BasicValueEnum<'a>>,
-    insertee: FunctionValue<'a>,
-) {
-    let bb = insertee
-        .get_first_basic_block()
-        .expect("Unable to find function to insert function call into");
-    let first_instruction = bb
-        .get_first_instruction()
-        .expect("Unable to find where to insert function call into function");
-    let builder = context.create_builder();
-    builder.position_before(&first_instruction);
-    builder.build_call(f, &args, "");
-}
-
-////////////////////////////////////////////////////////////////
-// Transformations associated with SeaHorn
-////////////////////////////////////////////////////////////////
+    let mut rules = Vec::new();
 
-fn handle_main(module: &Module, otest: &Option<String>) {
-    // Remove the main function rustc generates.
-    if let Some(main) = module.get_function("main") {
-        unsafe {
-            // Any use of `main` after calling `delete` is unsafe
-            main.delete();
-        }
-        info!("Deleted 'main' (was added by rustc).");
+    if opt.seahorn {
+        let pass = if opt.harness {
+            SeahornMainPass::harness()
+        } else {
+            SeahornMainPass::new(opt.test.clone())
+        };
+        outputs.entry = pass.entry.clone();
+        pipeline.add(pass);
+
+        rules.extend(seahorn_stub_rules());
     }
 
-    let re = match otest {
-        Some(test) => {
-            Regex::new(&format!("{}{}{}", test.len(), &test, r"17h[a-f0-9]{16}E$")).unwrap()
+    match &opt.panic_handler {
+        Some(handler) => {
+            pipeline.add(PanicRedirectPass::new(handler));
         }
-        None => Regex::new(r"4main17h[a-f0-9]{16}E$").unwrap(),
-    };
-
-    // Print the entry point function name
-    if let Some(fun) = get_function(module, &re) {
-        // Change the linkage of mangled main function from internal to external.
-        // fun.set_linkage(Linkage::External);
-        println!("ENTRY: {}", fun.get_name().to_str().unwrap());
-    }
-}
-
-fn get_function<'ctx>(module: &'ctx Module, re: &Regex) -> Option<FunctionValue<'ctx>> {
-    let mut op_fun = module.get_first_function();
-    while let Some(fun) = op_fun {
-        if re.is_match(
-            fun.get_name()
-                .to_str()
-                .expect("ERROR: function name is not in valid UTF-8"),
-        ) {
-            return Some(fun);
+        None if opt.seahorn => {
+            // (Defined by the SeaHorn backend of verification-annotations.)
+            pipeline.add(PanicRedirectPass::optional("spanic"));
         }
-        op_fun = fun.get_next_function();
+        None => (),
     }
-    None
-}
 
-fn handle_panic(module: &Module) {
-    // TODO: make "spanic" a CL arg.
-    if let Some(spanic) = module.get_function("spanic") {
-        if let Some(unwind) = module.get_function("rust_begin_unwind") {
-            unwind.replace_all_uses_with(spanic);
-            info!("Replaced panic handling ('rust_begin_unwind') with 'spanic'.");
-        }
+    if let Some(path) = &opt.stub_config {
+        rules.extend(read_stub_config(path)?.stub);
     }
-}
-
-fn replace_def_with_dec(module: &Module, re: &Regex) {
-    if let Some(fun) = get_function(module, re) {
-        for bb in fun.get_basic_blocks() {
-            unsafe {
-                // Any use of `bb` after calling `delete` is unsafe
-                bb.delete().unwrap();
-            }
-        }
-        fun.remove_personality_function();
-        fun.set_linkage(Linkage::External);
-        info!(
-            "Removed the implementation of '{}'.",
-            fun.get_name().to_str().unwrap()
-        );
+    if !rules.is_empty() {
+        pipeline.add(StubPass::Rules {
+            rules,
+            seahorn: opt.seahorn,
+        });
     }
+
+    Ok((pipeline, outputs))
 }
 
 ////////////////////////////////////////////////////////////////
@@ -0,0 +1,81 @@
+////////////////////////////////////////////////////////////////
+// Non-deterministic values
+//
+// SeaHorn creates non-deterministic values with the functions
+// '__VERIFIER_nondet_*' and KLEE makes memory symbolic using
+// 'klee_make_symbolic' so the passes that create values
+// (e.g., the harness and the 'nondet' stub action) need to
+// know which verifier the module is for.
+////////////////////////////////////////////////////////////////
+
+use inkwell::builder::Builder;
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::types::IntType;
+use inkwell::values::IntValue;
+
+use crate::error::Result;
+use crate::llvm;
+
+/// Build a non-deterministic integer of type 'ty' called 'name'
+/// (using SeaHorn's '__VERIFIER_nondet_*' functions if 'seahorn' is set
+/// and using 'klee_make_symbolic' otherwise).
+///
+/// The width of 'ty' must be 8, 16, 32 or 64 bits for SeaHorn.
+pub fn build_nondet_int<'a>(
+    context: &'a Context,
+    module: &Module<'a>,
+    builder: &Builder<'a>,
+    ty: IntType<'a>,
+    name: &str,
+    seahorn: bool,
+) -> Result<IntValue<'a>> {
+    if seahorn {
+        build_seahorn_nondet_int(module, builder, ty, name)
+    } else {
+        build_klee_symbolic_int(context, module, builder, ty, name)
+    }
+}
+
+/// Build a call to '__VERIFIER_nondet_uN'
+fn build_seahorn_nondet_int<'a>(
+    module: &Module<'a>,
+    builder: &Builder<'a>,
+    ty: IntType<'a>,
+    name: &str,
+) -> Result<IntValue<'a>> {
+    let nondet_name = format!("__VERIFIER_nondet_u{}", ty.get_bit_width());
+    let nondet = module
+        .get_function(&nondet_name)
+        .unwrap_or_else(|| module.add_function(&nondet_name, ty.fn_type(&[], false), None));
+    let call = builder.build_call(nondet, &[], name)?;
+    Ok(llvm::call_result(call)?.into_int_value())
+}
+
+/// Build a symbolic integer using 'klee_make_symbolic'
+fn build_klee_symbolic_int<'a>(
+    context: &'a Context,
+    module: &Module<'a>,
+    builder: &Builder<'a>,
+    ty: IntType<'a>,
+    name: &str,
+) -> Result<IntValue<'a>> {
+    let i64_type = context.i64_type();
+    let pi8_type = llvm::i8_ptr_type(context);
+    let make_symbolic = module.get_function("klee_make_symbolic").unwrap_or_else(|| {
+        let fn_type = context
+            .void_type()
+            .fn_type(&[pi8_type.into(), i64_type.into(), pi8_type.into()], false);
+        module.add_function("klee_make_symbolic", fn_type, None)
+    });
+    let p = builder.build_alloca(ty, &format!("{}.ptr", name))?;
+    let data = builder.build_pointer_cast(p, pi8_type, "")?;
+    let size = i64_type.const_int(u64::from(ty.get_bit_width() / 8), false);
+    let desc = builder.build_global_string_ptr(name, "")?.as_pointer_value();
+    builder.build_call(make_symbolic, &[data.into(), size.into(), desc.into()], "")?;
+    Ok(llvm::build_load(builder, ty, p, name)?.into_int_value())
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
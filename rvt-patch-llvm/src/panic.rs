@@ -0,0 +1,163 @@
+////////////////////////////////////////////////////////////////
+// Panic handling
+//
+// Verifiers do not need to model unwinding (or the output of the
+// default panic hook) if panics are redirected to a verifier hook
+// that reports an error. The hook has the type of the panic handler
+//
+//     fn(info: Option<&PanicInfo>) -> !
+//
+// ('info' is None when the panic message is not available) and
+// we redirect
+//
+// - 'rust_begin_unwind' (the panic handler): every panic that has
+//   a message ends up here so the hook is passed the 'PanicInfo'
+// - functions in 'core::panicking' that are declared but not defined
+//   (defined functions are kept because they pass the message on
+//   to 'rust_begin_unwind')
+// - 'std::process::abort'
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::module::Module;
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue};
+use log::{info, warn};
+
+use crate::error::{Error, Result};
+use crate::functions::{get_functions_by_path, path_pattern};
+use crate::pass::Pass;
+use crate::stubs::delete_body;
+
+/// Functions that are redirected if they are not defined
+const PANIC_DECLARATIONS: &str = "core::panicking::panic*";
+
+/// Functions that are always redirected
+const ABORTS: &[&str] = &["std::process::abort"];
+
+/// Redirect panics and aborts to the function 'handler'.
+///
+/// It is an error if the module does not contain 'handler' unless
+/// 'optional' is set.
+pub struct PanicRedirectPass {
+    pub handler: String,
+    pub optional: bool,
+}
+
+impl PanicRedirectPass {
+    pub fn new(handler: &str) -> PanicRedirectPass {
+        PanicRedirectPass {
+            handler: handler.to_string(),
+            optional: false,
+        }
+    }
+
+    /// Redirect panics to 'handler' if the module contains it
+    pub fn optional(handler: &str) -> PanicRedirectPass {
+        PanicRedirectPass {
+            handler: handler.to_string(),
+            optional: true,
+        }
+    }
+}
+
+impl Pass for PanicRedirectPass {
+    fn name(&self) -> &str {
+        "panic-redirect"
+    }
+
+    fn run<'ctx>(&self, context: &'ctx Context, module: &Module<'ctx>) -> Result<()> {
+        match module.get_function(&self.handler) {
+            Some(handler) => handle_panic(context, module, handler),
+            None if self.optional => {
+                info!("Not redirecting panics because there is no '{}'.", self.handler);
+                Ok(())
+            }
+            None => Err(Error::MissingFunction(self.handler.clone())),
+        }
+    }
+}
+
+fn handle_panic<'a>(context: &'a Context, module: &Module<'a>, handler: FunctionValue<'a>) -> Result<()> {
+    let name = handler.get_name().to_string_lossy();
+
+    if let Some(unwind) = module.get_function("rust_begin_unwind") {
+        if unwind.get_type() == handler.get_type() {
+            unwind.replace_all_uses_with(handler);
+            info!("Replaced panic handling ('rust_begin_unwind') with '{}'.", name);
+        } else {
+            // (With typed pointers, 'PanicInfo' can have more than one name.)
+            redirect(context, unwind, handler, true)?;
+        }
+    }
+
+    let mut targets: Vec<FunctionValue> = get_functions_by_path(module, &path_pattern(PANIC_DECLARATIONS)?)?
+        .into_iter()
+        .filter(|f| f.count_basic_blocks() == 0)
+        .collect();
+    for path in ABORTS {
+        targets.extend(get_functions_by_path(module, &path_pattern(path)?)?);
+    }
+    for target in targets {
+        if target != handler {
+            redirect(context, target, handler, false)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the body of 'target' (or give a declaration a body)
+/// with a call to 'handler'
+///
+///     define void @rust_begin_unwind(i8* %0) {
+///     entry:
+///       call void @handler(i8* %0)
+///       unreachable
+///     }
+///
+/// If 'forward' is set, the arguments of 'target' are passed to 'handler'
+/// (cast to the types of its parameters if they are pointers).
+/// Zero and null pointers are passed for any other parameters.
+fn redirect<'a>(
+    context: &'a Context,
+    target: FunctionValue<'a>,
+    handler: FunctionValue<'a>,
+    forward: bool,
+) -> Result<()> {
+    let name = target.get_name().to_string_lossy();
+    let params = target.get_params();
+    let builder = context.create_builder();
+
+    delete_body(target)?;
+    let basic_block = context.append_basic_block(target, "entry");
+    builder.position_at_end(basic_block);
+
+    let mut args: Vec<BasicMetadataValueEnum> = Vec::new();
+    for (i, p) in handler.get_params().iter().enumerate() {
+        let ty = p.get_type();
+        let arg: BasicValueEnum = match params.get(i) {
+            Some(a) if forward && a.get_type() == ty => *a,
+            Some(a) if forward && a.is_pointer_value() && ty.is_pointer_type() => builder
+                .build_pointer_cast(a.into_pointer_value(), ty.into_pointer_type(), "")?
+                .into(),
+            Some(_) if forward => {
+                warn!("Not passing argument {} of '{}' to the panic handler.", i, name);
+                ty.const_zero()
+            }
+            _ => ty.const_zero(),
+        };
+        args.push(arg.into());
+    }
+    builder.build_call(handler, &args, "")?;
+    builder.build_unreachable()?;
+
+    info!(
+        "Replaced the implementation of '{}' with a call to '{}'.",
+        name,
+        handler.get_name().to_string_lossy()
+    );
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,99 @@
+////////////////////////////////////////////////////////////////
+// Passes
+//
+// Each transformation is a pass that modifies a module in place.
+// A pipeline runs a sequence of passes in order (the order matters:
+// e.g., the harness must be built before initializer calls are
+// inserted into it).
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::module::Module;
+use log::info;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::Result;
+
+/// A transformation of an LLVM module
+pub trait Pass {
+    /// Name of the pass (used in log messages)
+    fn name(&self) -> &str;
+
+    /// Transform 'module' (which belongs to 'context')
+    fn run<'ctx>(&self, context: &'ctx Context, module: &Module<'ctx>) -> Result<()>;
+}
+
+/// A result produced by a pass (e.g., the entry point that it selects)
+///
+/// Passes are owned by the pipeline that runs them so the creator of
+/// a pass keeps a clone of the output to read the result afterwards.
+///
+/// ```ignore
+/// let pass = SeahornMainPass::new(None);
+/// let entry = pass.entry.clone();
+/// pipeline.add(pass);
+/// pipeline.run(&context, &module)?;
+/// println!("{:?}", entry.get());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Output<T>(Rc<RefCell<Option<T>>>);
+
+impl<T> Default for Output<T> {
+    fn default() -> Output<T> {
+        Output(Rc::new(RefCell::new(None)))
+    }
+}
+
+impl<T: Clone> Output<T> {
+    /// The result (None if the pass has not run)
+    pub fn get(&self) -> Option<T> {
+        self.0.borrow().clone()
+    }
+
+    pub(crate) fn set(&self, value: T) {
+        *self.0.borrow_mut() = Some(value);
+    }
+}
+
+/// A sequence of passes
+///
+///     let mut pipeline = Pipeline::new();
+///     pipeline
+///         .add(InitializersPass::new(vec!["main".to_string()]))
+///         .add(StubPass::Attributes);
+///     pipeline.run(&context, &module)?;
+#[derive(Default)]
+pub struct Pipeline {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Add a pass to the end of the pipeline
+    pub fn add(&mut self, pass: impl Pass + 'static) -> &mut Pipeline {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// The names of the passes (in the order that they are run)
+    pub fn names(&self) -> Vec<&str> {
+        self.passes.iter().map(|p| p.name()).collect()
+    }
+
+    /// Run each pass in turn (stopping at the first error)
+    pub fn run<'ctx>(&self, context: &'ctx Context, module: &Module<'ctx>) -> Result<()> {
+        for pass in &self.passes {
+            info!("Running pass '{}'", pass.name());
+            pass.run(context, module)?;
+        }
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,97 @@
+////////////////////////////////////////////////////////////////
+// Transformations associated with SeaHorn
+//
+// SeaHorn verifies a single entry point (which scripts learn from
+// the 'ENTRY:' line that rvt-patch-llvm prints) and cannot handle
+// some of the functions in std.
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::module::Module;
+use log::info;
+
+use crate::error::{symbol_name, Error, Result};
+use crate::functions::find_function;
+use crate::pass::{Output, Pass};
+use crate::stubs::{StubAction, StubRule};
+
+/// Select the entry point for SeaHorn and set 'entry' to its symbol name.
+///
+/// The entry point is the test 'test' (or 'main' if there is no test)
+/// and the 'main' function generated by rustc is deleted.
+/// If 'keep_main' is set (because 'main' is a harness built by
+/// `HarnessPass`), 'main' is kept and is the entry point.
+pub struct SeahornMainPass {
+    pub test: Option<String>,
+    pub keep_main: bool,
+    pub entry: Output<String>,
+}
+
+impl SeahornMainPass {
+    pub fn new(test: Option<String>) -> SeahornMainPass {
+        SeahornMainPass {
+            test,
+            keep_main: false,
+            entry: Output::default(),
+        }
+    }
+
+    /// Use the harness built by `HarnessPass` as the entry point
+    pub fn harness() -> SeahornMainPass {
+        SeahornMainPass {
+            test: None,
+            keep_main: true,
+            entry: Output::default(),
+        }
+    }
+}
+
+impl Pass for SeahornMainPass {
+    fn name(&self) -> &str {
+        "seahorn-main"
+    }
+
+    fn run<'ctx>(&self, _context: &'ctx Context, module: &Module<'ctx>) -> Result<()> {
+        let entry = if self.keep_main {
+            "main".to_string()
+        } else {
+            handle_main(module, &self.test)?
+        };
+        self.entry.set(entry);
+        Ok(())
+    }
+}
+
+/// Returns the symbol name of the entry point
+fn handle_main(module: &Module, otest: &Option<String>) -> Result<String> {
+    // Remove the main function rustc generates.
+    if let Some(main) = module.get_function("main") {
+        unsafe {
+            // Any use of `main` after calling `delete` is unsafe
+            main.delete();
+        }
+        info!("Deleted 'main' (was added by rustc).");
+    }
+
+    let selector = otest.as_deref().unwrap_or("main");
+
+    let fun = find_function(module, selector)?.ok_or_else(|| Error::MissingFunction(selector.to_string()))?;
+    // Change the linkage of mangled main function from internal to external.
+    // fun.set_linkage(Linkage::External);
+    symbol_name(fun.get_name())
+}
+
+/// Functions that SeaHorn cannot handle
+pub fn seahorn_stub_rules() -> Vec<StubRule> {
+    ["std::io::stdio::_eprint", "std::io::stdio::_print"]
+        .iter()
+        .map(|path| StubRule {
+            path: path.to_string(),
+            action: StubAction::Declare,
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
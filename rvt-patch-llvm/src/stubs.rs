@@ -0,0 +1,247 @@
+////////////////////////////////////////////////////////////////
+// Transformations associated with stubs
+//
+// Functions can be replaced by stubs written using
+// `#[verifier::stub(path)]` or by rules in a stub configuration file.
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::module::{Linkage, Module};
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::FunctionValue;
+use log::{info, warn};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::error::{symbol_name, Error, Result};
+use crate::functions::{get_functions, get_functions_by_path, path_pattern};
+use crate::llvm;
+use crate::nondet::build_nondet_int;
+use crate::pass::Pass;
+
+/// Replace functions with stubs
+pub enum StubPass {
+    /// Redirect calls to functions that have stubs (see `#[verifier::stub]`)
+    Attributes,
+    /// Apply the rules of a stub configuration
+    /// (creating non-deterministic values for SeaHorn if 'seahorn' is set
+    /// and for KLEE otherwise)
+    Rules { rules: Vec<StubRule>, seahorn: bool },
+}
+
+impl Pass for StubPass {
+    fn name(&self) -> &str {
+        match self {
+            StubPass::Attributes => "stub-attributes",
+            StubPass::Rules { .. } => "stub-rules",
+        }
+    }
+
+    fn run<'ctx>(&self, context: &'ctx Context, module: &Module<'ctx>) -> Result<()> {
+        match self {
+            StubPass::Attributes => handle_stubs(module),
+            StubPass::Rules { rules, seahorn } => handle_stub_rules(context, module, rules, *seahorn),
+        }
+    }
+}
+
+/// Prefix of the symbol names given to stubs by `#[verifier::stub(path)]`
+const STUB_PREFIX: &str = "__verifier_stub::";
+
+/// Redirect all uses of functions that have stubs to the stubs.
+///
+/// The stub of a function with the path 'path' is the function
+/// called '__verifier_stub::path'.
+fn handle_stubs(module: &Module) -> Result<()> {
+    let stub_re = Regex::new(&format!("^{}", regex::escape(STUB_PREFIX)))?;
+    for stub in get_functions(module, &stub_re)? {
+        let stub_name = symbol_name(stub.get_name())?;
+        let path = &stub_name[STUB_PREFIX.len()..];
+
+        // Generic functions can have several instances
+        let originals = get_functions_by_path(module, &path_pattern(path)?)?;
+        if originals.is_empty() {
+            warn!("Unable to find function '{}' to replace with a stub.", path);
+        }
+        for original in originals {
+            let name = original.get_name().to_string_lossy();
+            if original.get_type() != stub.get_type() {
+                warn!(
+                    "Not replacing '{}' with '{}' because their types are different.",
+                    name, stub_name
+                );
+                continue;
+            }
+            original.replace_all_uses_with(stub);
+            info!("Replaced '{}' with '{}'.", name, stub_name);
+        }
+    }
+    Ok(())
+}
+
+/// One entry of a stub configuration file.
+///
+///     [[stub]]
+///     path = "std::thread::spawn"
+///     action = "replace"
+///     with = "my_spawn"
+///
+/// In paths, '*' matches any part of a path segment and
+/// '**' matches any sequence of path segments.
+#[derive(Deserialize)]
+pub struct StubRule {
+    pub path: String,
+    #[serde(flatten)]
+    pub action: StubAction,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum StubAction {
+    /// Remove the body of the function (turning it into a declaration)
+    Declare,
+    /// Replace all uses of the function with the function 'with'
+    /// (either a symbol name or a path)
+    Replace { with: String },
+    /// Replace the body of the function with one that returns
+    /// a non-deterministic value
+    Nondet,
+}
+
+#[derive(Deserialize)]
+pub struct StubConfig {
+    #[serde(default)]
+    pub stub: Vec<StubRule>,
+}
+
+pub fn read_stub_config(path: &Path) -> Result<StubConfig> {
+    info!("Reading stub configuration from {}", path.display());
+    let config_error = |message: String| Error::Config {
+        path: path.to_path_buf(),
+        message,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| config_error(e.to_string()))?;
+    toml::from_str(&text).map_err(|e| config_error(e.to_string()))
+}
+
+fn handle_stub_rules<'a>(context: &'a Context, module: &Module<'a>, rules: &[StubRule], seahorn: bool) -> Result<()> {
+    for rule in rules {
+        let funs = get_functions_by_path(module, &path_pattern(&rule.path)?)?;
+        if funs.is_empty() {
+            info!("No functions match '{}'.", rule.path);
+        }
+        for fun in funs {
+            match &rule.action {
+                StubAction::Declare => replace_def_with_dec(fun)?,
+                StubAction::Replace { with } => replace_function(module, fun, with)?,
+                StubAction::Nondet => replace_def_with_nondet(context, module, fun, seahorn)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn replace_def_with_dec(fun: FunctionValue) -> Result<()> {
+    delete_body(fun)?;
+    fun.set_linkage(Linkage::External);
+    info!(
+        "Removed the implementation of '{}'.",
+        fun.get_name().to_string_lossy()
+    );
+    Ok(())
+}
+
+/// Delete the body of a function (turning it into a declaration)
+pub fn delete_body(fun: FunctionValue) -> Result<()> {
+    for bb in fun.get_basic_blocks() {
+        unsafe {
+            // Any use of `bb` after calling `delete` is unsafe
+            bb.delete().map_err(|_| {
+                Error::Llvm(format!(
+                    "failed to delete the body of '{}'",
+                    fun.get_name().to_string_lossy()
+                ))
+            })?;
+        }
+    }
+    llvm::remove_personality_function(fun);
+    Ok(())
+}
+
+/// Replace all uses of 'fun' with the function 'with'
+/// (a symbol name or a path).
+fn replace_function(module: &Module, fun: FunctionValue, with: &str) -> Result<()> {
+    let name = fun.get_name().to_string_lossy();
+    let replacement = match module.get_function(with) {
+        Some(f) => Some(f),
+        None => {
+            let fs = get_functions_by_path(module, &path_pattern(with)?)?;
+            if fs.len() > 1 {
+                warn!("Not replacing '{}' because '{}' is ambiguous.", name, with);
+                return Ok(());
+            }
+            fs.into_iter().next()
+        }
+    };
+    match replacement {
+        Some(f) if f.get_type() == fun.get_type() => {
+            fun.replace_all_uses_with(f);
+            info!("Replaced '{}' with '{}'.", name, f.get_name().to_string_lossy());
+        }
+        Some(_) => warn!(
+            "Not replacing '{}' with '{}' because their types are different.",
+            name, with
+        ),
+        None => warn!("Unable to find function '{}' to replace '{}'.", with, name),
+    }
+    Ok(())
+}
+
+/// Replace the body of 'fun' with one that returns a non-deterministic value
+/// (see `build_nondet_int`).
+///
+/// Only functions that return integers (or nothing) can be replaced.
+fn replace_def_with_nondet<'a>(
+    context: &'a Context,
+    module: &Module<'a>,
+    fun: FunctionValue<'a>,
+    seahorn: bool,
+) -> Result<()> {
+    let name = fun.get_name().to_string_lossy();
+    let int_type = match fun.get_type().get_return_type() {
+        None => None,
+        Some(BasicTypeEnum::IntType(t)) if t.get_bit_width() <= 64 => Some(t),
+        Some(_) => {
+            warn!("Not replacing '{}' because its return type is not an integer.", name);
+            return Ok(());
+        }
+    };
+
+    delete_body(fun)?;
+    let basic_block = context.append_basic_block(fun, "entry");
+    let builder = context.create_builder();
+    builder.position_at_end(basic_block);
+    match int_type {
+        None => {
+            builder.build_return(None)?;
+        }
+        Some(t) => {
+            // bool (i1) values are created from a u8
+            let width = std::cmp::max(t.get_bit_width(), 8).next_power_of_two();
+            let nondet_type = context.custom_width_int_type(width);
+            let r = build_nondet_int(context, module, &builder, nondet_type, "r", seahorn)?;
+            let r = if width == t.get_bit_width() {
+                r
+            } else {
+                builder.build_int_truncate(r, t, "r")?
+            };
+            builder.build_return(Some(&r))?;
+        }
+    }
+    info!("Replaced the implementation of '{}' with a non-deterministic value.", name);
+    Ok(())
+}
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
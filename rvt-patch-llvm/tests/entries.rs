@@ -0,0 +1,128 @@
+////////////////////////////////////////////////////////////////
+// Tests of 'rvt-patch-llvm --list-entries' and '--expectations'
+//
+// The fixture 'descriptors.ll' contains the test descriptors
+// generated by an old and a recent version of rustc. There are two
+// versions of the fixture: 'typed' (LLVM 10-14) and 'opaque'
+// (LLVM 15 onwards).
+////////////////////////////////////////////////////////////////
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+const OPAQUE_POINTERS: bool = cfg!(any(
+    feature = "llvm15-0",
+    feature = "llvm16-0",
+    feature = "llvm17-0",
+    feature = "llvm18-0"
+));
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(if OPAQUE_POINTERS { "opaque" } else { "typed" })
+        .join(name)
+}
+
+/// The entries listed by 'rvt-patch-llvm --list-entries' (indexed by path)
+fn list_entries() -> BTreeMap<String, Value> {
+    let output = Command::new(env!("CARGO_BIN_EXE_rvt-patch-llvm"))
+        .arg(fixture("descriptors.ll"))
+        .arg("--list-entries")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let entries: Vec<Value> = serde_json::from_slice(&output.stdout).unwrap();
+    entries
+        .into_iter()
+        .map(|e| (e["path"].as_str().unwrap().to_string(), e))
+        .collect()
+}
+
+#[test]
+fn entries() {
+    let entries = list_entries();
+    let paths: Vec<&str> = entries.keys().map(|p| p.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "t::new::both",
+            "t::new::ig",
+            "t::new::igr",
+            "t::new::plain",
+            "t::new::sp",
+            "t::new::spe",
+            "t::old::ig",
+            "t::old::plain",
+            "t::old::sp",
+            "t::old::spe",
+        ]
+    );
+    assert_eq!(entries["t::old::sp"]["name"], "old::sp");
+    assert_eq!(
+        entries["t::old::sp"]["symbol"],
+        "_ZN1t3old2sp17h6b192fd1e364a63aE"
+    );
+    assert_eq!(
+        entries["t::new::sp"]["symbol"],
+        "_RNvNtCskOGwlKjLXpR_1t3new2sp"
+    );
+    // (The test function is inlined into the closure.)
+    assert_eq!(entries["t::old::plain"]["symbol"], Value::Null);
+}
+
+#[test]
+fn should_panic() {
+    let entries = list_entries();
+    let expected = |path: &str| entries[path]["should_panic"]["expected"].clone();
+    for crate_path in &["t::old", "t::new"] {
+        assert_eq!(expected(&format!("{}::spe", crate_path)), "boom");
+        // #[should_panic] without an expected message
+        let sp = &entries[&format!("{}::sp", crate_path)]["should_panic"];
+        assert!(sp.is_object());
+        assert_eq!(sp["expected"], Value::Null);
+        for test in &["plain", "ig"] {
+            assert_eq!(
+                entries[&format!("{}::{}", crate_path, test)]["should_panic"],
+                Value::Null
+            );
+        }
+    }
+    // The reason for ignoring a test is not an expected message
+    assert_eq!(entries["t::new::igr"]["should_panic"], Value::Null);
+    assert_eq!(expected("t::new::both"), "bang");
+}
+
+#[test]
+fn expectations() {
+    let path = std::env::temp_dir().join("rvt-patch-llvm-expectations.json");
+    let status = Command::new(env!("CARGO_BIN_EXE_rvt-patch-llvm"))
+        .arg(fixture("descriptors.ll"))
+        .arg("-o")
+        .arg(std::env::temp_dir().join("rvt-patch-llvm-descriptors.ll"))
+        .arg("--expectations")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let json: BTreeMap<String, String> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let expected: BTreeMap<String, String> = vec![
+        ("new::both", "should_panic(expected = \"bang\")"),
+        ("new::sp", "should_panic"),
+        ("new::spe", "should_panic(expected = \"boom\")"),
+        ("old::sp", "should_panic"),
+        ("old::spe", "should_panic(expected = \"boom\")"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    assert_eq!(json, expected);
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,150 @@
+; Test descriptors generated by rustc for the tests
+;
+;   t::old::{plain, sp, spe, ig}            (rustc 1.46, legacy mangling)
+;   t::new::{plain, sp, spe, ig, igr, both} (rustc 1.9x, v0 mangling)
+;
+; where 'sp' is #[should_panic], 'spe' is #[should_panic(expected = "boom")],
+; 'ig' is #[ignore], 'igr' is #[ignore = "slow"] and 'both' is
+; #[ignore = "slow"] with #[should_panic(expected = "bang")].
+; (Only the descriptors, their strings and the functions that run the tests are kept.)
+
+; rustc 1.46: the descriptors refer to instances of 'core::ops::function::FnOnce::call_once'
+; that call the closures
+
+@old.rs = private unnamed_addr constant [6 x i8] c"old.rs", align 1
+@boom = private unnamed_addr constant [4 x i8] c"boom", align 1
+@old.plain = private unnamed_addr constant [10 x i8] c"old::plain", align 1
+@old.sp = private unnamed_addr constant [7 x i8] c"old::sp", align 1
+@old.spe = private unnamed_addr constant [8 x i8] c"old::spe", align 1
+@old.ig = private unnamed_addr constant [7 x i8] c"old::ig", align 1
+
+@old.plain.desc = private unnamed_addr constant <{ [8 x i8], ptr, [64 x i8], ptr, [8 x i8] }> <{ [8 x i8] zeroinitializer, ptr @old.plain, [64 x i8] c"\0A\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\03\00\00\00\00\00\00\00\00\00\00\00\00\00", ptr @_ZN4core3ops8function6FnOnce9call_once17h9e241189ed52b5e2E, [8 x i8] zeroinitializer }>, align 8
+@old.sp.desc = private unnamed_addr constant <{ [8 x i8], ptr, [64 x i8], ptr, [8 x i8] }> <{ [8 x i8] zeroinitializer, ptr @old.sp, [64 x i8] c"\07\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\03\00\00\00\00\00\00\00\00\00\00\00\00\00", ptr @_ZN4core3ops8function6FnOnce9call_once17h100746b276f9c6cfE, [8 x i8] zeroinitializer }>, align 8
+@old.spe.desc = private unnamed_addr constant <{ [8 x i8], ptr, [32 x i8], ptr, [24 x i8], ptr, [8 x i8] }> <{ [8 x i8] zeroinitializer, ptr @old.spe, [32 x i8] c"\08\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\02\00\00\00\00\00\00\00", ptr @boom, [24 x i8] c"\04\00\00\00\00\00\00\00\00\00\03\00\00\00\00\00\00\00\00\00\00\00\00\00", ptr @_ZN4core3ops8function6FnOnce9call_once17hf5b62b714994e6acE, [8 x i8] zeroinitializer }>, align 8
+@old.ig.desc = private unnamed_addr constant <{ [8 x i8], ptr, [64 x i8], ptr, [8 x i8] }> <{ [8 x i8] zeroinitializer, ptr @old.ig, [64 x i8] c"\07\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\01\00\03\00\00\00\00\00\00\00\00\00\00\00\00\00", ptr @_ZN4core3ops8function6FnOnce9call_once17hbed06532c7a597bfE, [8 x i8] zeroinitializer }>, align 8
+
+; rustc 1.9x: the descriptors refer to '<{closure} as FnOnce>::call_once'
+
+@new.rs = private unnamed_addr constant [6 x i8] c"new.rs", align 1
+@bang = private unnamed_addr constant [4 x i8] c"bang", align 1
+@slow = private unnamed_addr constant [4 x i8] c"slow", align 1
+@new.plain = private unnamed_addr constant [10 x i8] c"new::plain", align 1
+@new.sp = private unnamed_addr constant [7 x i8] c"new::sp", align 1
+@new.spe = private unnamed_addr constant [8 x i8] c"new::spe", align 1
+@new.ig = private unnamed_addr constant [7 x i8] c"new::ig", align 1
+@new.igr = private unnamed_addr constant [8 x i8] c"new::igr", align 1
+@new.both = private unnamed_addr constant [9 x i8] c"new::both", align 1
+
+@new.plain.desc = private unnamed_addr constant <{ [8 x i8], [16 x i8], [8 x i8], ptr, [8 x i8], [8 x i8], ptr, [16 x i8], [8 x i8], [36 x i8], [4 x i8], [8 x i8], ptr, [8 x i8] }> <{ [8 x i8] zeroinitializer, [16 x i8] undef, [8 x i8] c"\00\00\00\00\00\00\00\80", ptr @new.plain, [8 x i8] c"\0A\00\00\00\00\00\00\00", [8 x i8] undef, ptr @new.rs, [16 x i8] c"\06\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00", [8 x i8] undef, [36 x i8] c"\03\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00\03\00\00\00\00\00\00\00\09\00\00\00\00\00\00\00\00\00\00\03", [4 x i8] undef, [8 x i8] zeroinitializer, ptr @_RNvYNCNvNtCskOGwlKjLXpR_1t3new5plain0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_, [8 x i8] undef }>, align 8
+@new.sp.desc = private unnamed_addr constant <{ [8 x i8], [16 x i8], [8 x i8], ptr, [8 x i8], [8 x i8], ptr, [16 x i8], [8 x i8], [36 x i8], [4 x i8], [8 x i8], ptr, [8 x i8] }> <{ [8 x i8] c"\01\00\00\00\00\00\00\00", [16 x i8] undef, [8 x i8] c"\00\00\00\00\00\00\00\80", ptr @new.sp, [8 x i8] c"\07\00\00\00\00\00\00\00", [8 x i8] undef, ptr @new.rs, [16 x i8] c"\06\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00", [8 x i8] undef, [36 x i8] c"\06\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00\06\00\00\00\00\00\00\00\06\00\00\00\00\00\00\00\00\00\00\03", [4 x i8] undef, [8 x i8] zeroinitializer, ptr @_RNvYNCNvNtCskOGwlKjLXpR_1t3new2sp0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_, [8 x i8] undef }>, align 8
+@new.spe.desc = private unnamed_addr constant <{ [8 x i8], ptr, [16 x i8], ptr, [8 x i8], [8 x i8], ptr, [16 x i8], [8 x i8], [36 x i8], [4 x i8], [8 x i8], ptr, [8 x i8] }> <{ [8 x i8] c"\02\00\00\00\00\00\00\00", ptr @boom, [16 x i8] c"\04\00\00\00\00\00\00\00\00\00\00\00\00\00\00\80", ptr @new.spe, [8 x i8] c"\08\00\00\00\00\00\00\00", [8 x i8] undef, ptr @new.rs, [16 x i8] c"\06\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00", [8 x i8] undef, [36 x i8] c"\09\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00\09\00\00\00\00\00\00\00\07\00\00\00\00\00\00\00\00\00\00\03", [4 x i8] undef, [8 x i8] zeroinitializer, ptr @_RNvYNCNvNtCskOGwlKjLXpR_1t3new3spe0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_, [8 x i8] undef }>, align 8
+@new.ig.desc = private unnamed_addr constant <{ [8 x i8], [16 x i8], [8 x i8], ptr, [8 x i8], [8 x i8], ptr, [16 x i8], [8 x i8], [36 x i8], [4 x i8], [8 x i8], ptr, [8 x i8] }> <{ [8 x i8] zeroinitializer, [16 x i8] undef, [8 x i8] c"\00\00\00\00\00\00\00\80", ptr @new.ig, [8 x i8] c"\07\00\00\00\00\00\00\00", [8 x i8] undef, ptr @new.rs, [16 x i8] c"\06\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00", [8 x i8] undef, [36 x i8] c"\0C\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00\0C\00\00\00\00\00\00\00\06\00\00\00\00\00\00\00\01\00\00\03", [4 x i8] undef, [8 x i8] zeroinitializer, ptr @_RNvYNCNvNtCskOGwlKjLXpR_1t3new2ig0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_, [8 x i8] undef }>, align 8
+@new.igr.desc = private unnamed_addr constant <{ [8 x i8], [16 x i8], [8 x i8], ptr, [8 x i8], [8 x i8], ptr, [8 x i8], ptr, [44 x i8], [4 x i8], [8 x i8], ptr, [8 x i8] }> <{ [8 x i8] zeroinitializer, [16 x i8] undef, [8 x i8] c"\00\00\00\00\00\00\00\80", ptr @new.igr, [8 x i8] c"\08\00\00\00\00\00\00\00", [8 x i8] undef, ptr @new.rs, [8 x i8] c"\06\00\00\00\00\00\00\00", ptr @slow, [44 x i8] c"\04\00\00\00\00\00\00\00\0F\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00\0F\00\00\00\00\00\00\00\07\00\00\00\00\00\00\00\01\00\00\03", [4 x i8] undef, [8 x i8] zeroinitializer, ptr @_RNvYNCNvNtCskOGwlKjLXpR_1t3new3igr0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_, [8 x i8] undef }>, align 8
+@new.both.desc = private unnamed_addr constant <{ [8 x i8], ptr, [16 x i8], ptr, [8 x i8], [8 x i8], ptr, [8 x i8], ptr, [44 x i8], [4 x i8], [8 x i8], ptr, [8 x i8] }> <{ [8 x i8] c"\02\00\00\00\00\00\00\00", ptr @bang, [16 x i8] c"\04\00\00\00\00\00\00\00\00\00\00\00\00\00\00\80", ptr @new.both, [8 x i8] c"\09\00\00\00\00\00\00\00", [8 x i8] undef, ptr @new.rs, [8 x i8] c"\06\00\00\00\00\00\00\00", ptr @slow, [44 x i8] c"\04\00\00\00\00\00\00\00\13\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00\13\00\00\00\00\00\00\00\08\00\00\00\00\00\00\00\01\00\00\03", [4 x i8] undef, [8 x i8] zeroinitializer, ptr @_RNvYNCNvNtCskOGwlKjLXpR_1t3new4both0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_, [8 x i8] undef }>, align 8
+
+; core::ops::function::FnOnce::call_once
+define internal void @_ZN4core3ops8function6FnOnce9call_once17h9e241189ed52b5e2E() {
+  call void @"_ZN1t3old5plain28_$u7b$$u7b$closure$u7d$$u7d$17hd33444cffaee1f15E"()
+  ret void
+}
+
+; t::old::plain::{{closure}}
+define internal void @"_ZN1t3old5plain28_$u7b$$u7b$closure$u7d$$u7d$17hd33444cffaee1f15E"() {
+  ret void
+}
+
+; core::ops::function::FnOnce::call_once
+define internal void @_ZN4core3ops8function6FnOnce9call_once17h100746b276f9c6cfE() {
+  call void @"_ZN1t3old2sp28_$u7b$$u7b$closure$u7d$$u7d$17hdd12530d791b6be9E"()
+  ret void
+}
+
+; t::old::sp::{{closure}}
+define internal void @"_ZN1t3old2sp28_$u7b$$u7b$closure$u7d$$u7d$17hdd12530d791b6be9E"() {
+  call void @_ZN1t3old2sp17h6b192fd1e364a63aE()
+  ret void
+}
+
+; t::old::sp
+define internal void @_ZN1t3old2sp17h6b192fd1e364a63aE() {
+  ret void
+}
+
+; core::ops::function::FnOnce::call_once
+define internal void @_ZN4core3ops8function6FnOnce9call_once17hf5b62b714994e6acE() {
+  call void @"_ZN1t3old3spe28_$u7b$$u7b$closure$u7d$$u7d$17hfa3667b155745389E"()
+  ret void
+}
+
+; t::old::spe::{{closure}}
+define internal void @"_ZN1t3old3spe28_$u7b$$u7b$closure$u7d$$u7d$17hfa3667b155745389E"() {
+  call void @_ZN1t3old3spe17hff4e04593c13c100E()
+  ret void
+}
+
+; t::old::spe
+define internal void @_ZN1t3old3spe17hff4e04593c13c100E() {
+  ret void
+}
+
+; core::ops::function::FnOnce::call_once
+define internal void @_ZN4core3ops8function6FnOnce9call_once17hbed06532c7a597bfE() personality ptr @rust_eh_personality {
+start:
+  invoke void @"_ZN1t3old2ig28_$u7b$$u7b$closure$u7d$$u7d$17h9f1ba8c5228027bdE"()
+          to label %bb1 unwind label %cleanup
+
+bb1:
+  ret void
+
+cleanup:
+  %0 = landingpad { ptr, i32 }
+          cleanup
+  resume { ptr, i32 } %0
+}
+
+; t::old::ig::{{closure}}
+define internal void @"_ZN1t3old2ig28_$u7b$$u7b$closure$u7d$$u7d$17h9f1ba8c5228027bdE"() {
+  ret void
+}
+
+declare i32 @rust_eh_personality(...)
+
+; <t::new::plain::{closure#0} as core::ops::function::FnOnce<()>>::call_once
+define internal void @_RNvYNCNvNtCskOGwlKjLXpR_1t3new5plain0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_() {
+  ret void
+}
+
+; <t::new::sp::{closure#0} as core::ops::function::FnOnce<()>>::call_once
+define internal void @_RNvYNCNvNtCskOGwlKjLXpR_1t3new2sp0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_() {
+  call void @_RNvNtCskOGwlKjLXpR_1t3new2sp()
+  ret void
+}
+
+; <t::new::spe::{closure#0} as core::ops::function::FnOnce<()>>::call_once
+define internal void @_RNvYNCNvNtCskOGwlKjLXpR_1t3new3spe0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_() {
+  ret void
+}
+
+; <t::new::ig::{closure#0} as core::ops::function::FnOnce<()>>::call_once
+define internal void @_RNvYNCNvNtCskOGwlKjLXpR_1t3new2ig0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_() {
+  ret void
+}
+
+; <t::new::igr::{closure#0} as core::ops::function::FnOnce<()>>::call_once
+define internal void @_RNvYNCNvNtCskOGwlKjLXpR_1t3new3igr0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_() {
+  ret void
+}
+
+; <t::new::both::{closure#0} as core::ops::function::FnOnce<()>>::call_once
+define internal void @_RNvYNCNvNtCskOGwlKjLXpR_1t3new4both0INtNtNtCs8NwYtU1Mohg_4core3ops8function6FnOnceuE9call_onceB8_() {
+  ret void
+}
+
+; t::new::sp
+define internal void @_RNvNtCskOGwlKjLXpR_1t3new2sp() {
+  ret void
+}
+
+define i32 @main() {
+  ret i32 0
+}
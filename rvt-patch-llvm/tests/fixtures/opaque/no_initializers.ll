@@ -0,0 +1,5 @@
+; A module without initializers
+
+define i32 @main(i32 %0, ptr %1) {
+  ret i32 0
+}
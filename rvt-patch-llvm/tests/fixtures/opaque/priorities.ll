@@ -0,0 +1,38 @@
+; Initializers with different priorities in sections and in llvm.global_ctors
+
+@init_200 = internal constant ptr @f200, section ".init_array.00200"
+@init_default = internal constant [2 x ptr] [ptr @fdefault1, ptr @fdefault2], section ".init_array"
+@init_100 = internal constant ptr @f100, section ".init_array.00100"
+@llvm.global_ctors = appending global [3 x { i32, ptr, ptr }] [
+  { i32, ptr, ptr } { i32 65535, ptr @c_default, ptr null },
+  { i32, ptr, ptr } { i32 150, ptr @c150, ptr null },
+  { i32, ptr, ptr } { i32 65535, ptr null, ptr null }
+]
+
+define internal void @f100() {
+  ret void
+}
+
+define internal void @c150() {
+  ret void
+}
+
+define internal void @f200() {
+  ret void
+}
+
+define internal void @fdefault1() {
+  ret void
+}
+
+define internal void @fdefault2() {
+  ret void
+}
+
+define internal void @c_default() {
+  ret void
+}
+
+define i32 @main(i32 %0, ptr %1) {
+  ret i32 0
+}
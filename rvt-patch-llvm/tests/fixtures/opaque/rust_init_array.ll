@@ -0,0 +1,12 @@
+; An initializer in the form that rustc generates for std's ARGV_INIT_ARRAY
+
+@ARGV_INIT_ARRAY = internal constant <{ ptr, [0 x i8] }> <{ ptr @argv_init, [0 x i8] zeroinitializer }>, section ".init_array.00099", align 8
+@llvm.used = appending global [1 x ptr] [ptr @ARGV_INIT_ARRAY], section "llvm.metadata"
+
+define internal void @argv_init(i32 %argc, ptr %argv, ptr %envp) {
+  ret void
+}
+
+define i32 @main(i32 %0, ptr %1) {
+  ret i32 0
+}
@@ -0,0 +1,19 @@
+; Initializers called from several entry points that take no arguments
+
+@init = internal constant [2 x i8*] [i8* bitcast (void ()* @ctor to i8*), i8* bitcast (i32 (i64)* @bad_ctor to i8*)], section ".init_array"
+
+define internal void @ctor() {
+  ret void
+}
+
+define internal i32 @bad_ctor(i64 %x) {
+  ret i32 0
+}
+
+define void @test1() {
+  ret void
+}
+
+define i32 @main() {
+  ret i32 0
+}
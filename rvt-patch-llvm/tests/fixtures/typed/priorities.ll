@@ -0,0 +1,38 @@
+; Initializers with different priorities in sections and in llvm.global_ctors
+
+@init_200 = internal constant i8* bitcast (void ()* @f200 to i8*), section ".init_array.00200"
+@init_default = internal constant [2 x void ()*] [void ()* @fdefault1, void ()* @fdefault2], section ".init_array"
+@init_100 = internal constant void ()* @f100, section ".init_array.00100"
+@llvm.global_ctors = appending global [3 x { i32, void ()*, i8* }] [
+  { i32, void ()*, i8* } { i32 65535, void ()* @c_default, i8* null },
+  { i32, void ()*, i8* } { i32 150, void ()* @c150, i8* null },
+  { i32, void ()*, i8* } { i32 65535, void ()* null, i8* null }
+]
+
+define internal void @f100() {
+  ret void
+}
+
+define internal void @c150() {
+  ret void
+}
+
+define internal void @f200() {
+  ret void
+}
+
+define internal void @fdefault1() {
+  ret void
+}
+
+define internal void @fdefault2() {
+  ret void
+}
+
+define internal void @c_default() {
+  ret void
+}
+
+define i32 @main(i32 %0, i8** %1) {
+  ret i32 0
+}
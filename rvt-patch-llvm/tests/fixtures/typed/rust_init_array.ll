@@ -0,0 +1,12 @@
+; An initializer in the form that rustc generates for std's ARGV_INIT_ARRAY
+
+@ARGV_INIT_ARRAY = internal constant <{ i8*, [0 x i8] }> <{ i8* bitcast (void (i32, i8**, i8**)* @argv_init to i8*), [0 x i8] zeroinitializer }>, section ".init_array.00099", align 8
+@llvm.used = appending global [1 x i8*] [i8* bitcast (<{ i8*, [0 x i8] }>* @ARGV_INIT_ARRAY to i8*)], section "llvm.metadata"
+
+define internal void @argv_init(i32 %argc, i8** %argv, i8** %envp) {
+  ret void
+}
+
+define i32 @main(i32 %0, i8** %1) {
+  ret i32 0
+}
@@ -0,0 +1,113 @@
+////////////////////////////////////////////////////////////////
+// Tests of 'rvt-patch-llvm --initializers'
+//
+// Each test patches an LLVM IR file in 'tests/fixtures' and checks
+// the calls in the patched functions. There are two versions of each
+// fixture: 'typed' (LLVM 10-14) and 'opaque' (LLVM 15 onwards).
+////////////////////////////////////////////////////////////////
+
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus};
+
+const OPAQUE_POINTERS: bool = cfg!(any(
+    feature = "llvm15-0",
+    feature = "llvm16-0",
+    feature = "llvm17-0",
+    feature = "llvm18-0"
+));
+
+/// How LLVM prints the type 'i8**'
+const PPI8: &str = if OPAQUE_POINTERS { "ptr" } else { "i8**" };
+
+/// Run rvt-patch-llvm on a fixture
+/// (returning the exit status and the name of the output file)
+fn run(fixture: &str, args: &[&str]) -> (ExitStatus, PathBuf) {
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(if OPAQUE_POINTERS { "opaque" } else { "typed" })
+        .join(fixture);
+    let output = std::env::temp_dir().join(format!("rvt-patch-llvm-{}", fixture));
+    let status = Command::new(env!("CARGO_BIN_EXE_rvt-patch-llvm"))
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .args(args)
+        .status()
+        .unwrap();
+    (status, output)
+}
+
+/// Run rvt-patch-llvm on a fixture and return the patched LLVM IR
+fn patch(fixture: &str, args: &[&str]) -> String {
+    let (status, output) = run(fixture, args);
+    assert!(status.success());
+    std::fs::read_to_string(&output).unwrap()
+}
+
+/// The names of the functions called by the function 'name' (in order)
+fn calls(ir: &str, name: &str) -> Vec<String> {
+    let header = format!("@{}(", name);
+    let body = ir
+        .split("\n}\n")
+        .find(|f| f.lines().any(|l| l.starts_with("define") && l.contains(&header)))
+        .unwrap_or_else(|| panic!("no function '{}'", name));
+    body.lines()
+        .filter(|l| !l.starts_with("define"))
+        .filter_map(|l| l.split(" call ").nth(1))
+        .map(|call| {
+            let callee = &call[call.find('@').unwrap() + 1..];
+            callee[..callee.find('(').unwrap()].to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn rust_init_array() {
+    let ir = patch("rust_init_array.ll", &["--initializers"]);
+    assert_eq!(calls(&ir, "main"), ["__init_function"]);
+    let call = format!("call void @__init_function(i32 %0, {} %1, {} null)", PPI8, PPI8);
+    assert!(ir.contains(&call));
+    assert_eq!(calls(&ir, "__init_function"), ["argv_init"]);
+}
+
+#[test]
+fn priorities() {
+    let ir = patch("priorities.ll", &["--initializers"]);
+    assert_eq!(
+        calls(&ir, "__init_function"),
+        ["f100", "c150", "f200", "fdefault1", "fdefault2", "c_default"]
+    );
+    // The initializers are only called once
+    assert!(!ir.contains("@llvm.global_ctors"));
+}
+
+#[test]
+fn entries() {
+    let args = ["--initializers", "--init-entry", "test1", "--init-entry", "main"];
+    let ir = patch("entries.ll", &args);
+    assert_eq!(calls(&ir, "test1"), ["__init_function"]);
+    assert_eq!(calls(&ir, "main"), ["__init_function"]);
+    let call = format!("call void @__init_function(i32 0, {} null, {} null)", PPI8, PPI8);
+    assert!(ir.contains(&call));
+    // 'bad_ctor' does not have the type of an initializer
+    assert_eq!(calls(&ir, "__init_function"), ["ctor"]);
+}
+
+#[test]
+fn no_initializers() {
+    let ir = patch("no_initializers.ll", &["--initializers"]);
+    assert!(calls(&ir, "main").is_empty());
+    assert!(!ir.contains("__init_function"));
+}
+
+#[test]
+fn missing_entry() {
+    // Selecting a function that does not exist is a usage error
+    let (status, _) = run("no_initializers.ll", &["--initializers", "--init-entry", "missing"]);
+    assert_eq!(status.code(), Some(1));
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
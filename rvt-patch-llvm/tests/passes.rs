@@ -0,0 +1,503 @@
+////////////////////////////////////////////////////////////////
+// Tests of the passes in the rvt_patch_llvm library
+//
+// Each test parses a small LLVM IR module from a string, runs some
+// passes on it and checks the result. Most modules do not use pointers
+// so that they are valid for all versions of LLVM (the panic hook module
+// uses typed or opaque pointers to match the version of LLVM).
+// (The harness tests use the test descriptors in 'tests/fixtures'.)
+////////////////////////////////////////////////////////////////
+
+use inkwell::context::Context;
+use inkwell::memory_buffer::MemoryBuffer;
+use inkwell::module::Module;
+use std::process::Command;
+
+use rvt_patch_llvm::{
+    Category, Error, HarnessPass, PanicRedirectPass, Pass, Pipeline, Result, SeahornMainPass, StubAction, StubPass,
+    StubRule,
+};
+
+/// Parse a module from LLVM IR
+fn parse<'ctx>(context: &'ctx Context, ir: &str) -> Module<'ctx> {
+    let buffer = MemoryBuffer::create_from_memory_range_copy(ir.as_bytes(), "test");
+    context.create_module_from_ir(buffer).unwrap()
+}
+
+/// Print a module as LLVM IR
+fn print(module: &Module) -> String {
+    module.print_to_string().to_string()
+}
+
+fn rule(path: &str, action: StubAction) -> StubRule {
+    StubRule {
+        path: path.to_string(),
+        action,
+    }
+}
+
+/// Stub rules for KLEE
+fn stub_rules(rules: Vec<StubRule>) -> StubPass {
+    StubPass::Rules { rules, seahorn: false }
+}
+
+// mylib::answer, mylib::stub_answer and mylib::flag
+const ANSWER: &str = "_ZN5mylib6answer17h0123456789abcdefE";
+const STUB_ANSWER: &str = "_ZN5mylib11stub_answer17h0123456789abcdefE";
+const FLAG: &str = "_ZN5mylib4flag17h0123456789abcdefE";
+
+fn stub_module(context: &Context) -> Module<'_> {
+    let ir = format!(
+        r#"
+define i32 @{answer}() {{
+  ret i32 42
+}}
+
+define i32 @{stub_answer}() {{
+  ret i32 0
+}}
+
+define i1 @{flag}() {{
+  ret i1 true
+}}
+
+define i32 @main() {{
+  %r = call i32 @{answer}()
+  ret i32 %r
+}}
+"#,
+        answer = ANSWER,
+        stub_answer = STUB_ANSWER,
+        flag = FLAG
+    );
+    parse(context, &ir)
+}
+
+#[test]
+fn stub_replace() {
+    let context = Context::create();
+    let module = stub_module(&context);
+    let with = "mylib::stub_answer".to_string();
+    let pass = stub_rules(vec![rule("mylib::answer", StubAction::Replace { with })]);
+    pass.run(&context, &module).unwrap();
+    let ir = print(&module);
+    assert!(ir.contains(&format!("call i32 @{}()", STUB_ANSWER)));
+    assert!(!ir.contains(&format!("call i32 @{}()", ANSWER)));
+}
+
+#[test]
+fn stub_declare() {
+    let context = Context::create();
+    let module = stub_module(&context);
+    let pass = stub_rules(vec![rule("mylib::*answer", StubAction::Declare)]);
+    pass.run(&context, &module).unwrap();
+    for name in &[ANSWER, STUB_ANSWER] {
+        assert_eq!(module.get_function(name).unwrap().count_basic_blocks(), 0);
+    }
+    assert_eq!(module.get_function(FLAG).unwrap().count_basic_blocks(), 1);
+}
+
+#[test]
+fn stub_nondet() {
+    let context = Context::create();
+    let module = stub_module(&context);
+    let pass = StubPass::Rules {
+        rules: vec![
+            rule("mylib::answer", StubAction::Nondet),
+            rule("mylib::flag", StubAction::Nondet),
+        ],
+        seahorn: true,
+    };
+    pass.run(&context, &module).unwrap();
+    let ir = print(&module);
+    assert!(ir.contains("call i32 @__VERIFIER_nondet_u32()"));
+    // bool values are created from a u8
+    assert!(ir.contains("call i8 @__VERIFIER_nondet_u8()"));
+    assert!(ir.contains("trunc i8"));
+}
+
+#[test]
+fn stub_nondet_klee() {
+    let context = Context::create();
+    let module = stub_module(&context);
+    let pass = stub_rules(vec![
+        rule("mylib::answer", StubAction::Nondet),
+        rule("mylib::flag", StubAction::Nondet),
+    ]);
+    pass.run(&context, &module).unwrap();
+    let ir = print(&module);
+    assert!(!ir.contains("__VERIFIER_nondet"));
+    assert_eq!(ir.matches("call void @klee_make_symbolic(").count(), 2);
+    assert!(ir.contains("alloca i32"));
+    assert!(ir.contains("alloca i8"));
+    assert!(ir.contains("trunc i8"));
+}
+
+/// A module with a stub for 'path' (with return type 'ty')
+/// defined by `#[verifier::stub(path)]`
+fn stub_attribute_module<'ctx>(context: &'ctx Context, path: &str, ty: &str) -> Module<'ctx> {
+    let ir = format!(
+        r#"
+define i32 @{answer}() {{
+  ret i32 42
+}}
+
+define i1 @{flag}() {{
+  ret i1 true
+}}
+
+define {ty} @"__verifier_stub::{path}"() {{
+  ret {ty} 7
+}}
+
+define i32 @main() {{
+  %r = call i32 @{answer}()
+  %f = call i1 @{flag}()
+  ret i32 %r
+}}
+"#,
+        answer = ANSWER,
+        flag = FLAG,
+        ty = ty,
+        path = path
+    );
+    parse(context, &ir)
+}
+
+#[test]
+fn stub_attribute() {
+    let context = Context::create();
+    let module = stub_attribute_module(&context, "mylib::answer", "i32");
+    StubPass::Attributes.run(&context, &module).unwrap();
+    let ir = print(&module);
+    assert!(ir.contains(r#"call i32 @"__verifier_stub::mylib::answer"()"#));
+    assert!(!ir.contains(&format!("call i32 @{}()", ANSWER)));
+}
+
+#[test]
+fn stub_attribute_type_mismatch() {
+    let context = Context::create();
+    let module = stub_attribute_module(&context, "mylib::flag", "i32");
+    StubPass::Attributes.run(&context, &module).unwrap();
+    let ir = print(&module);
+    assert!(ir.contains(&format!("call i1 @{}()", FLAG)));
+    assert!(!ir.contains(r#"call i32 @"__verifier_stub::mylib::flag"()"#));
+}
+
+#[test]
+fn stub_attribute_missing() {
+    let context = Context::create();
+    let module = stub_attribute_module(&context, "mylib::missing", "i32");
+    let before = print(&module);
+    // A stub without an original only causes a warning
+    StubPass::Attributes.run(&context, &module).unwrap();
+    assert_eq!(print(&module), before);
+}
+
+#[test]
+fn panic_redirect() {
+    let ir = r#"
+declare void @rust_begin_unwind()
+
+define void @verifier_panic() {
+  ret void
+}
+
+define void @main() {
+  call void @rust_begin_unwind()
+  ret void
+}
+"#;
+    let context = Context::create();
+    let module = parse(&context, ir);
+    PanicRedirectPass::new("verifier_panic")
+        .run(&context, &module)
+        .unwrap();
+    let ir = print(&module);
+    assert!(ir.contains("call void @verifier_panic()"));
+    assert!(!ir.contains("call void @rust_begin_unwind()"));
+}
+
+// core::panicking::panic, core::panicking::panic_fmt and std::process::abort
+const PANIC: &str = "_ZN4core9panicking5panic17h0123456789abcdefE";
+const PANIC_FMT: &str = "_ZN4core9panicking9panic_fmt17h0123456789abcdefE";
+const ABORT: &str = "_ZN3std7process5abort17h0123456789abcdefE";
+
+#[test]
+fn panic_redirect_functions() {
+    let ir = format!(
+        r#"
+declare void @rust_begin_unwind(i64)
+
+declare void @{panic}(i64, i64)
+
+define void @{panic_fmt}(i64 %0) {{
+  call void @rust_begin_unwind(i64 %0)
+  unreachable
+}}
+
+define void @{abort}() {{
+  call void @abort()
+  unreachable
+}}
+
+declare void @abort()
+
+define void @verifier_panic(i64 %0, i32 %1) {{
+  ret void
+}}
+"#,
+        panic = PANIC,
+        panic_fmt = PANIC_FMT,
+        abort = ABORT
+    );
+    let context = Context::create();
+    let module = parse(&context, &ir);
+    PanicRedirectPass::new("verifier_panic")
+        .run(&context, &module)
+        .unwrap();
+    let ir = print(&module);
+    // The arguments of 'rust_begin_unwind' are passed on if they have the
+    // types of the handler's parameters and zero is passed otherwise.
+    assert!(ir.contains("call void @verifier_panic(i64 %0, i32 0)"));
+    assert!(ir.contains("call void @verifier_panic(i64 0, i32 0)"));
+    assert!(!ir.contains("call void @abort()"));
+    for name in &["rust_begin_unwind", PANIC, ABORT] {
+        assert_eq!(module.get_function(name).unwrap().count_basic_blocks(), 1);
+    }
+    // Defined panic functions pass the message on to 'rust_begin_unwind'
+    assert!(ir.contains("call void @rust_begin_unwind(i64 %0)"));
+}
+
+#[test]
+fn panic_handler_missing() {
+    let context = Context::create();
+    let module = stub_module(&context);
+    match PanicRedirectPass::new("missing").run(&context, &module) {
+        Err(Error::MissingFunction(name)) => assert_eq!(name, "missing"),
+        _ => panic!("expected a missing function error"),
+    }
+    assert!(PanicRedirectPass::optional("missing")
+        .run(&context, &module)
+        .is_ok());
+}
+
+/// A module with the panic functions and the KLEE hook
+///
+///     fn klee_panic(info: Option<&PanicInfo>) -> !
+///
+/// (With typed pointers, the hook and 'rust_begin_unwind' use
+/// different names for 'PanicInfo'.)
+fn panic_hook_module(context: &Context) -> Module<'_> {
+    let (info, hook_info, pi8) = if OPAQUE_POINTERS {
+        ("ptr", "ptr", "ptr")
+    } else {
+        (
+            r#"%"core::panic::PanicInfo"*"#,
+            r#"%"core::panic::PanicInfo.1"*"#,
+            "i8*",
+        )
+    };
+    let types = if OPAQUE_POINTERS {
+        ""
+    } else {
+        r#"
+%"core::panic::PanicInfo" = type { i8*, i64 }
+%"core::panic::PanicInfo.1" = type { i8*, i64 }
+"#
+    };
+    let ir = format!(
+        r#"
+{types}
+declare void @rust_begin_unwind({info})
+
+declare void @{panic}({pi8}, i64)
+
+define void @klee_panic({hook_info} %0) {{
+  unreachable
+}}
+
+define void @main({info} %0) {{
+  call void @rust_begin_unwind({info} %0)
+  call void @{panic}({pi8} null, i64 0)
+  ret void
+}}
+"#,
+        types = types,
+        info = info,
+        hook_info = hook_info,
+        pi8 = pi8,
+        panic = PANIC
+    );
+    parse(context, &ir)
+}
+
+#[test]
+fn panic_redirect_hook() {
+    let context = Context::create();
+    let module = panic_hook_module(&context);
+    PanicRedirectPass::new("klee_panic")
+        .run(&context, &module)
+        .unwrap();
+    // The redirected calls type-check
+    module.verify().unwrap();
+    let ir = print(&module);
+    if OPAQUE_POINTERS {
+        assert!(ir.contains("call void @klee_panic(ptr %0)"));
+        assert!(ir.contains("call void @klee_panic(ptr null)"));
+    } else {
+        assert!(ir.contains(r#"call void @klee_panic(%"core::panic::PanicInfo.1"* %1)"#));
+        assert!(ir.contains(r#"call void @klee_panic(%"core::panic::PanicInfo.1"* null)"#));
+    }
+    for name in &["rust_begin_unwind", PANIC] {
+        assert_eq!(module.get_function(name).unwrap().count_basic_blocks(), 1);
+    }
+}
+
+const OPAQUE_POINTERS: bool = cfg!(any(
+    feature = "llvm15-0",
+    feature = "llvm16-0",
+    feature = "llvm17-0",
+    feature = "llvm18-0"
+));
+
+/// The fixture containing test descriptors
+fn descriptors_fixture() -> &'static str {
+    if OPAQUE_POINTERS {
+        include_str!("fixtures/opaque/descriptors.ll")
+    } else {
+        include_str!("fixtures/typed/descriptors.ll")
+    }
+}
+
+// The tests in 'descriptors.ll' that can be called from a harness:
+// t::new::sp, t::old::sp and t::old::spe
+const NEW_SP: &str = "_RNvNtCskOGwlKjLXpR_1t3new2sp";
+const OLD_SP: &str = "_ZN1t3old2sp17h6b192fd1e364a63aE";
+const OLD_SPE: &str = "_ZN1t3old3spe17hff4e04593c13c100E";
+
+/// The body of the 'main' function built by the harness pass
+fn harness_main(module: &Module) -> String {
+    let ir = print(module);
+    let main = ir.split("define i32 @main(").nth(1).unwrap();
+    main[..main.find("\n}\n").unwrap()].to_string()
+}
+
+#[test]
+fn harness() {
+    let context = Context::create();
+    let module = parse(&context, descriptors_fixture());
+    HarnessPass::new(vec![], false)
+        .run(&context, &module)
+        .unwrap();
+    let main = harness_main(&module);
+    assert!(main.contains("switch i32 %selector, label %done ["));
+    for (i, name) in [NEW_SP, OLD_SP, OLD_SPE].iter().enumerate() {
+        assert!(main.contains(&format!("i32 {}, label %test{}", i, i)));
+        assert!(main.contains(&format!("test{}:", i)));
+        assert!(main.contains(&format!("call void @{}()", name)));
+    }
+    assert!(main.contains("call void @klee_make_symbolic("));
+}
+
+#[test]
+fn harness_selected() {
+    let context = Context::create();
+    let module = parse(&context, descriptors_fixture());
+    let pass = HarnessPass::new(vec!["old::sp".to_string(), "t::new::sp".to_string()], true);
+    pass.run(&context, &module).unwrap();
+    let main = harness_main(&module);
+    assert!(main.contains("%selector = call i32 @__VERIFIER_nondet_u32()"));
+    assert!(main.contains(&format!("call void @{}()", NEW_SP)));
+    assert!(main.contains(&format!("call void @{}()", OLD_SP)));
+    assert!(!main.contains(&format!("call void @{}()", OLD_SPE)));
+    assert_eq!(pass.tests.get().unwrap(), ["t::new::sp", "t::old::sp"]);
+}
+
+#[test]
+fn seahorn_entry() {
+    let context = Context::create();
+    let module = parse(&context, descriptors_fixture());
+    let pass = SeahornMainPass::new(Some("old::sp".to_string()));
+    let entry = pass.entry.clone();
+    assert_eq!(entry.get(), None);
+    let mut pipeline = Pipeline::new();
+    pipeline.add(pass);
+    pipeline.run(&context, &module).unwrap();
+    assert_eq!(entry.get().unwrap(), OLD_SP);
+}
+
+#[test]
+fn harness_listing() {
+    let fixture = std::env::temp_dir().join("rvt-patch-llvm-harness.ll");
+    std::fs::write(&fixture, descriptors_fixture()).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rvt-patch-llvm"))
+        .arg(&fixture)
+        .arg("-o")
+        .arg(std::env::temp_dir().join("rvt-patch-llvm-harness-out.ll"))
+        .arg("--harness")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().filter(|l| l.starts_with("HARNESS:")).collect();
+    assert_eq!(
+        lines,
+        [
+            "HARNESS: 0 t::new::sp",
+            "HARNESS: 1 t::old::sp",
+            "HARNESS: 2 t::old::spe"
+        ]
+    );
+}
+
+/// A pass defined outside the library: adds the function 'added'
+struct AddFunction;
+
+impl Pass for AddFunction {
+    fn name(&self) -> &str {
+        "add-function"
+    }
+
+    fn run<'ctx>(&self, context: &'ctx Context, module: &Module<'ctx>) -> Result<()> {
+        let f = module.add_function("added", context.void_type().fn_type(&[], false), None);
+        let builder = context.create_builder();
+        builder.position_at_end(context.append_basic_block(f, "entry"));
+        builder.build_return(None)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn pipeline_order() {
+    let context = Context::create();
+    let module = stub_module(&context);
+    let mut pipeline = Pipeline::new();
+    pipeline
+        .add(AddFunction)
+        .add(stub_rules(vec![rule("mylib::answer", StubAction::Declare)]));
+    assert_eq!(pipeline.names(), ["add-function", "stub-rules"]);
+    pipeline.run(&context, &module).unwrap();
+    assert!(module.get_function("added").is_some());
+    assert_eq!(module.get_function(ANSWER).unwrap().count_basic_blocks(), 0);
+}
+
+#[test]
+fn pipeline_error() {
+    let context = Context::create();
+    let module = stub_module(&context);
+    let mut pipeline = Pipeline::new();
+    pipeline
+        .add(SeahornMainPass::new(Some("missing".to_string())))
+        .add(AddFunction);
+    match pipeline.run(&context, &module) {
+        Err(e @ Error::MissingFunction(_)) => assert_eq!(e.category(), Category::Usage),
+        _ => panic!("expected a missing function error"),
+    }
+    // The pipeline stops at the first error
+    assert!(module.get_function("added").is_none());
+}
+
+////////////////////////////////////////////////////////////////
+// End
+////////////////////////////////////////////////////////////////
@@ -32,6 +32,8 @@ Descritopn...
 -n, --no-lto                Don't add '-Clto' to RUSTFLAGS.
 -i, --input FILE            Run SH on FILE.
 -m, --mode MODE
+-r, --replay                Replay counterexamples natively (requires --crate).
+-s, --stub-config FILE      Stub configuration passed to rvt-patch-llvm.
 ENDUSAGE
 }
 
@@ -47,7 +49,7 @@ parse_cmd()
         CMDNAME="$0"
     fi
 
-    if ! args=$(getopt -o 'hc:t:f:ni:m:' -l 'help,crate:,test:,features:,no-lto,input:,mode:' --name "${CMDNAME}" -- "$@"); then
+    if ! args=$(getopt -o 'hc:t:f:ni:m:rs:' -l 'help,crate:,test:,features:,no-lto,input:,mode:,replay,stub-config:' --name "${CMDNAME}" -- "$@"); then
         usage >&2
         exit 2
     fi
@@ -87,6 +89,14 @@ parse_cmd()
                 MODE="$2"
                 shift 2
                 ;;
+            '-r'|'--replay')
+                shift
+                REPLAY=true
+                ;;
+            '-s'|'--stub-config')
+                readonly STUB_CONFIG="$2"
+                shift 2
+                ;;
             '--')
                 shift
                 break
@@ -102,6 +112,7 @@ parse_cmd()
 
     # Default values:
     : "${LTO:=true}"
+    : "${REPLAY:=false}"
     : "${MODE:=ybpf}"
 
     : "${VCC:="${SCRIPTDIR}/../../verify-c-common"}"
@@ -129,12 +140,34 @@ pp_rvt-patch-llvm()
 {
     if [[ ! "${INPUT}" =~ \.rvt\.(.*\.)*ll$ ]]; then
         output="${TEMPDIR}/$(bname "${INPUT}").rvt.ll"
-        { ENTRY="$("${RVTPATCHLLVM}" -vv -s ${TEST:+-t "${TEST}"} -o "${output}" "${INPUT}" | tee /dev/fd/3 | sed -n 's/^ENTRY: //p')"; } 3>&1
+        { ENTRY="$("${RVTPATCHLLVM}" -vv -s --stub ${STUB_CONFIG:+--stub-config "${STUB_CONFIG}"} ${TEST:+-t "${TEST}"} -o "${output}" "${INPUT}" | tee /dev/fd/3 | sed -n 's/^ENTRY: //p')"; } 3>&1
         SEAFLAGS=(--entry="${ENTRY}" "${SEAFLAGS[@]}")
         INPUT="${output}"
     fi
 }
 
+# Rebuild the crate natively, linking the counterexample harness generated by
+# SeaHorn, and run it so that the counterexample values are printed.
+replay()
+{
+    local harness="${TEMPDIR}/cex.ll"
+    if [[ ! -f "${harness}" ]]; then
+        echo "No counterexample to replay."
+        return
+    fi
+
+    # The verification RUSTFLAGS (LTO, bitcode, ...) are not needed for replay
+    # and using a separate target directory avoids rebuilding the bitcode.
+    local cargo_env=(env -u RUSTFLAGS
+                     SEAHORN_CEX_HARNESS="$(realpath "${harness}")"
+                     CARGO_TARGET_DIR="target/seahorn-replay")
+    if [[ -n "${TEST}" ]]; then
+        "${cargo_env[@]}" "${CARGO[@]}" test ${FEATURES:+--features "$FEATURES"} "${TEST}" -- --nocapture || true
+    else
+        "${cargo_env[@]}" "${CARGO[@]}" run ${FEATURES:+--features "$FEATURES"} || true
+    fi
+}
+
 CARGO=(cargo)
 # CARGO=(cargo +stage2-for-seahorn -v)
 
@@ -147,6 +180,8 @@ main()
 
         # Generate bitcode
         RUSTFLAGS="-Cembed-bitcode=yes --emit=llvm-bc ${RUSTFLAGS}"
+        # Enable verification-only code (such as stubs)
+        RUSTFLAGS="--cfg=verify ${RUSTFLAGS}"
         # Check overflow
         RUSTFLAGS="-Warithmetic-overflow -Coverflow-checks=yes ${RUSTFLAGS}"
         # Abort, instead of unwind
@@ -172,15 +207,27 @@ main()
 
     pp_rvt-patch-llvm
 
+    if [[ "${REPLAY}" == "true" ]]; then
+        rm -f "${TEMPDIR}/cex.ll"
+        SEAFLAGS=(--cex="${TEMPDIR}/cex.ll" "${SEAFLAGS[@]}")
+    fi
+
     # Run SeaHorn
+    local status=0
     case "${MODE}" in
         'ybpf')
-            sea yama -y "$VCC/seahorn/sea_base.yaml" bpf "${INPUT}" --temp-dir "${TEMPDIR}" "${SEAFLAGS[@]}"
+            sea yama -y "$VCC/seahorn/sea_base.yaml" bpf "${INPUT}" --temp-dir "${TEMPDIR}" "${SEAFLAGS[@]}" || status=$?
             ;;
         *)
-            sea "${MODE}" "${INPUT}" --temp-dir "${TEMPDIR}" "${SEAFLAGS[@]}"
+            sea "${MODE}" "${INPUT}" --temp-dir "${TEMPDIR}" "${SEAFLAGS[@]}" || status=$?
             ;;
     esac
+
+    if [[ "${REPLAY}" == "true" && -n "${CRATE}" ]]; then
+        replay
+    fi
+
+    return "${status}"
 }
 
 parse_cmd "$@"
@@ -0,0 +1,247 @@
+// Copyright 2021 The Propverify authors
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/////////////////////////////////////////////////////////////////
+// Function contracts
+//
+//     #[verifier::requires(x < 100)]
+//     #[verifier::ensures(|r| r > x)]
+//     fn increment(x: u32) -> u32 { x + 1 }
+//
+// Each contracted function is expanded into two versions and
+// the `contracts` feature of `verification-annotations` selects
+// which one is used
+//
+// - The checked version (the default) assumes the preconditions
+//   on entry and asserts the postconditions on exit so verifying
+//   a harness that calls the function checks the contract.
+//
+// - The replaced version (with the `contracts` feature) asserts the
+//   preconditions and then, instead of running the body, returns
+//   an abstract value that satisfies the postconditions. This lets
+//   callers be verified using the contract of the function instead
+//   of its body.
+//
+// The first contract attribute on a function handles all the others
+// so that the contract is expanded once.
+//
+// The generated code refers to the `verification_annotations` crate
+// unless one of the attributes gives another path for it
+//
+//     #[verifier::requires(crate = "propverify::prelude::verifier", x < 100)]
+/////////////////////////////////////////////////////////////////
+
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::{Attribute, Expr, ExprClosure, ItemFn, LitStr, Pat, ReturnType, Token};
+
+use crate::{default_crate, parse_crate};
+
+/// A single clause of a contract
+/// (and the path of `verification_annotations` if the attribute gives one)
+pub struct Clause {
+    krate: Option<syn::Path>,
+    kind: ClauseKind,
+}
+
+enum ClauseKind {
+    Requires(Expr),
+    Ensures(Pat, Expr),
+}
+
+impl Clause {
+    /// Parse the arguments of a `requires` attribute
+    pub fn requires(args: TokenStream) -> syn::Result<Clause> {
+        let parser = |input: ParseStream| {
+            let krate = crate_override(input)?;
+            let cond: Expr = input.parse()?;
+            Ok(Clause {
+                krate,
+                kind: ClauseKind::Requires(cond),
+            })
+        };
+        parser.parse2(args)
+    }
+
+    /// Parse the arguments of an `ensures` attribute
+    pub fn ensures(args: TokenStream) -> syn::Result<Clause> {
+        let parser = |input: ParseStream| {
+            let krate = crate_override(input)?;
+            let closure: ExprClosure = input.parse()?;
+            if closure.inputs.len() != 1 {
+                return Err(syn::Error::new(
+                    closure.inputs.span(),
+                    "expected a closure with one parameter (the result)",
+                ));
+            }
+            Ok(Clause {
+                krate,
+                kind: ClauseKind::Ensures(closure.inputs[0].clone(), *closure.body),
+            })
+        };
+        parser.parse2(args)
+    }
+
+    /// Parse a contract attribute (if `attr` is one)
+    fn from_attribute(attr: &Attribute) -> Option<syn::Result<Clause>> {
+        let name = attr.path.segments.last()?.ident.to_string();
+        let args = attr.parse_args::<TokenStream>();
+        match name.as_str() {
+            "requires" => Some(args.and_then(Clause::requires)),
+            "ensures" => Some(args.and_then(Clause::ensures)),
+            _ => None,
+        }
+    }
+}
+
+/// Expand a function with contract `first` (and any further
+/// contract attributes on the function).
+pub fn expand(first: Clause, mut item: ItemFn) -> syn::Result<TokenStream> {
+    if let Some(a) = &item.sig.asyncness {
+        return Err(syn::Error::new(a.span(), "contracts are not supported on async functions"));
+    }
+
+    let mut clauses = vec![first];
+    let mut attrs = Vec::new();
+    for attr in item.attrs.drain(..) {
+        match Clause::from_attribute(&attr) {
+            Some(clause) => clauses.push(clause?),
+            None => attrs.push(attr),
+        }
+    }
+
+    let krate = clauses
+        .iter()
+        .find_map(|c| c.krate.clone())
+        .unwrap_or_else(default_crate);
+
+    let mut pre_checked = Vec::new();
+    let mut pre_replaced = Vec::new();
+    let mut post_checked = Vec::new();
+    let mut post_replaced = Vec::new();
+    for clause in &clauses {
+        match &clause.kind {
+            ClauseKind::Requires(cond) => {
+                let message = format!("precondition failed: {}", quote!(#cond));
+                pre_checked.push(quote!(#krate::assume(#cond);));
+                pre_replaced.push(quote!(#krate::assert!(#cond, "{}", #message);));
+            }
+            ClauseKind::Ensures(pat, cond) => {
+                let message = format!("postcondition failed: {}", quote!(#cond));
+                post_checked.push(postcondition(pat, quote!(#krate::assert!(#cond, "{}", #message);)));
+                post_replaced.push(postcondition(pat, quote!(#krate::assume(#cond);)));
+            }
+        }
+    }
+
+    let vis = &item.vis;
+    let sig = &item.sig;
+    let block = &item.block;
+
+    // The body is run in a closure so that `return` and `?` do not skip
+    // the postconditions.
+    // (The type of the result cannot be written if it is `impl Trait`.)
+    let (run_body, replaced_body) = match &item.sig.output {
+        ReturnType::Default => (
+            quote!(let __verifier_result: () = (|| #block)();),
+            quote!(let __verifier_result = ();),
+        ),
+        ReturnType::Type(_, ty) if contains_impl_trait(quote!(#ty)) => (
+            quote!(let __verifier_result = (|| #block)();),
+            quote!(let __verifier_result = ::core::compile_error!(
+                "the `contracts` feature cannot replace functions that return `impl Trait`"
+            );),
+        ),
+        ReturnType::Type(_, ty) => (
+            quote!(let __verifier_result: #ty = (|| -> #ty #block)();),
+            quote!(let __verifier_result: #ty = #krate::AbstractValue::abstract_value();),
+        ),
+    };
+
+    Ok(quote! {
+        #krate::__select_contract! {
+            checked {
+                #(#attrs)*
+                #vis #sig {
+                    #(#pre_checked)*
+                    #[allow(clippy::redundant_closure_call)]
+                    #run_body
+                    #(#post_checked)*
+                    __verifier_result
+                }
+            }
+            replaced {
+                #(#attrs)*
+                #[allow(unused_variables, unused_mut)]
+                #vis #sig {
+                    #(#pre_replaced)*
+                    #replaced_body
+                    #(#post_replaced)*
+                    __verifier_result
+                }
+            }
+        }
+    })
+}
+
+/// Parse `crate = "path",` at the start of the arguments of a contract attribute
+fn crate_override(input: ParseStream) -> syn::Result<Option<syn::Path>> {
+    if !(input.peek(Token![crate]) && input.peek2(Token![=])) {
+        return Ok(None);
+    }
+    input.parse::<Token![crate]>()?;
+    input.parse::<Token![=]>()?;
+    let path = parse_crate(&input.parse::<LitStr>()?)?;
+    input.parse::<Token![,]>()?;
+    Ok(Some(path))
+}
+
+/// Bind the result to the parameter `pat` of a postcondition and run `check`.
+///
+/// If the parameter is a variable, the result is moved into it and then
+/// moved back so the result does not need to implement `Clone`.
+/// Other patterns are matched against a clone of the result.
+fn postcondition(pat: &Pat, check: TokenStream) -> TokenStream {
+    match variable(pat) {
+        Some(var) => quote! {
+            let __verifier_result = {
+                let #pat = __verifier_result;
+                #check
+                #var
+            };
+        },
+        None => quote!({
+            let #pat = ::core::clone::Clone::clone(&__verifier_result);
+            #check
+        }),
+    }
+}
+
+/// The variable bound by `pat` if it is `x`, `mut x` or `x: T`
+fn variable(pat: &Pat) -> Option<&Ident> {
+    match pat {
+        Pat::Ident(p) if p.by_ref.is_none() && p.subpat.is_none() => Some(&p.ident),
+        Pat::Type(p) => variable(&p.pat),
+        _ => None,
+    }
+}
+
+/// Whether a type contains `impl Trait`
+fn contains_impl_trait(ty: TokenStream) -> bool {
+    ty.into_iter().any(|t| match t {
+        TokenTree::Ident(i) => i == "impl",
+        TokenTree::Group(g) => contains_impl_trait(g.stream()),
+        _ => false,
+    })
+}
+
+/////////////////////////////////////////////////////////////////
+// End
+/////////////////////////////////////////////////////////////////
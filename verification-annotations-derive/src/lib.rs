@@ -0,0 +1,341 @@
+// Copyright 2021 The Propverify authors
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/////////////////////////////////////////////////////////////////
+// Derive macros for the AbstractValue and Symbolic traits
+//
+// Structs are built field by field and enums are built by choosing
+// a symbolic discriminant and then building the fields of the
+// chosen variant.
+//
+// Fields can be constrained with an invariant
+//
+//     #[derive(AbstractValue)]
+//     struct Range {
+//         #[verifier(assume = "lo <= hi")]
+//         lo: u32,
+//         hi: u32,
+//     }
+//
+// The invariant can refer to any field of the struct (or variant).
+// Fields of tuple structs are named `_0`, `_1`, etc.
+//
+// The generated code refers to the `verification_annotations` crate
+// so that crate must be a dependency of the crate using the derive.
+// Crates that only use it through another crate (e.g., through
+// `propverify::prelude::verifier`) can give the path to use instead
+//
+//     #[derive(AbstractValue)]
+//     #[verifier(crate = "propverify::prelude::verifier")]
+//     struct Range { ... }
+//
+// This crate also provides the `requires` and `ensures` attributes
+// for function contracts (see contracts.rs) and the `stub` attribute
+// for replacing functions during verification.
+/////////////////////////////////////////////////////////////////
+
+extern crate proc_macro;
+
+mod contracts;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Expr, Fields, ItemFn, Lit, LitStr, Meta, NestedMeta};
+
+use crate::contracts::Clause;
+
+/// Derive `AbstractValue` for a struct or enum
+#[proc_macro_derive(AbstractValue, attributes(verifier))]
+pub fn derive_abstract_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, Kind::AbstractValue)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derive `Symbolic` for a struct or enum
+#[proc_macro_derive(Symbolic, attributes(verifier))]
+pub fn derive_symbolic(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, Kind::Symbolic)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Add a precondition to a function
+///
+/// The condition can be preceded by `crate = "path",` to give the path
+/// of the `verification_annotations` crate (see `#[verifier(crate = "...")]`).
+#[proc_macro_attribute]
+pub fn requires(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as ItemFn);
+    Clause::requires(attr.into())
+        .and_then(|clause| contracts::expand(clause, item))
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Add a postcondition to a function
+///
+/// The postcondition is a closure that is passed the result
+/// (or a clone of the result if its parameter is not a variable).
+/// Like `requires`, it can be preceded by `crate = "path",`.
+#[proc_macro_attribute]
+pub fn ensures(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as ItemFn);
+    Clause::ensures(attr.into())
+        .and_then(|clause| contracts::expand(clause, item))
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Replace a function by this function during verification
+///
+/// ```ignore
+/// #[verifier::stub(std::time::Instant::now)]
+/// fn now() -> std::time::Instant { ... }
+/// ```
+///
+/// The stub is given the symbol name `__verifier_stub::<path>`
+/// and `rvt-patch-llvm --stub` redirects all calls to the function
+/// `path` to the stub. The stub must have the same signature as the
+/// function that it replaces and the path must start with the name
+/// of the crate that defines the function.
+///
+/// The symbol name is only set when compiling with `--cfg=verify`
+/// (as `cargo-verify` does) so that normal builds of crates that stub
+/// the same function do not define the same symbol.
+/// Within a single verification build, each function can only have one stub.
+#[proc_macro_attribute]
+pub fn stub(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(attr as syn::Path);
+    let item = parse_macro_input!(item as ItemFn);
+    stub_symbol(&path)
+        .map(|symbol| quote! {
+            #[cfg_attr(verify, export_name = #symbol)]
+            #item
+        })
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// The symbol name of a stub for the function `path`
+fn stub_symbol(path: &syn::Path) -> syn::Result<String> {
+    let mut segments = Vec::new();
+    for s in &path.segments {
+        if !s.arguments.is_empty() {
+            return Err(syn::Error::new(s.arguments.span(), "stub paths cannot have generic arguments"));
+        }
+        segments.push(s.ident.to_string());
+    }
+    Ok(format!("__verifier_stub::{}", segments.join("::")))
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    AbstractValue,
+    Symbolic,
+}
+
+/// The path of the `verification_annotations` crate used by generated code
+/// (unless it is overridden using `crate = "path"`)
+fn default_crate() -> syn::Path {
+    parse_quote!(::verification_annotations)
+}
+
+impl Kind {
+    fn trait_path(self, krate: &syn::Path) -> TokenStream {
+        match self {
+            Kind::AbstractValue => quote!(#krate::AbstractValue),
+            Kind::Symbolic => quote!(#krate::Symbolic),
+        }
+    }
+
+    /// Generate an expression that creates a value of type `ty`.
+    /// (`Symbolic` describes the value as `desc.part` where `desc`
+    /// describes the value being built.)
+    fn value(self, krate: &syn::Path, ty: &syn::Type, part: &str) -> TokenStream {
+        let tr = self.trait_path(krate);
+        match self {
+            Kind::AbstractValue => quote!(<#ty as #tr>::abstract_value()),
+            Kind::Symbolic => quote!(<#ty as #tr>::symbolic(#krate::__describe(desc, #part))),
+        }
+    }
+}
+
+fn expand(input: &DeriveInput, kind: Kind) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let krate = crate_path(&input.attrs)?;
+    let tr = kind.trait_path(&krate);
+
+    // Every type parameter must also implement the trait
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#tr));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(s) => build_fields(kind, &krate, "", quote!(#name), &s.fields)?,
+        Data::Enum(e) => {
+            if e.variants.is_empty() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "cannot create values of an enum with no variants",
+                ));
+            }
+            let n = e.variants.len() as u32;
+            let last = n - 1;
+            let mut arms = Vec::new();
+            for (i, v) in e.variants.iter().enumerate() {
+                let vname = &v.ident;
+                let prefix = format!("{}.", vname);
+                let build = build_fields(kind, &krate, &prefix, quote!(#name::#vname), &v.fields)?;
+                // The last variant is the catch-all arm
+                if i as u32 == last {
+                    arms.push(quote!(_ => #build));
+                } else {
+                    let i = i as u32;
+                    arms.push(quote!(#i => #build));
+                }
+            }
+            let discriminant = kind.value(&krate, &parse_quote!(u32), "discriminant");
+            quote! {
+                let discriminant: u32 = #discriminant;
+                #krate::assume(discriminant < #n);
+                match discriminant {
+                    #(#arms,)*
+                }
+            }
+        }
+        Data::Union(u) => {
+            return Err(syn::Error::new(
+                u.union_token.span(),
+                "cannot derive verifier traits for unions",
+            ))
+        }
+    };
+
+    let method = match kind {
+        Kind::AbstractValue => quote! {
+            fn abstract_value() -> Self {
+                #body
+            }
+        },
+        Kind::Symbolic => quote! {
+            // (`desc` is not used if there are no fields.)
+            #[allow(unused_variables)]
+            fn symbolic(desc: &'static str) -> Self {
+                #body
+            }
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics #tr for #name #ty_generics #where_clause {
+            #method
+        }
+    })
+}
+
+/// Generate a block that builds each field, adds any field
+/// invariants and then constructs the value using `ctor`.
+///
+/// Fields are described as `desc.<prefix><field>` (the prefix is
+/// used for the name of an enum variant).
+fn build_fields(
+    kind: Kind,
+    krate: &syn::Path,
+    prefix: &str,
+    ctor: TokenStream,
+    fields: &Fields,
+) -> syn::Result<TokenStream> {
+    let mut bindings = Vec::new();
+    let mut assumptions = Vec::new();
+    let mut names = Vec::new();
+
+    for (i, f) in fields.iter().enumerate() {
+        let var = match &f.ident {
+            Some(id) => id.clone(),
+            None => format_ident!("_{}", i),
+        };
+        let ty = &f.ty;
+        let value = kind.value(krate, ty, &format!("{}{}", prefix, var));
+        bindings.push(quote!(let #var: #ty = #value;));
+        for cond in field_assumptions(f)? {
+            assumptions.push(quote!(#krate::assume(#cond);));
+        }
+        names.push(var);
+    }
+
+    let construct = match fields {
+        Fields::Named(_) => quote!(#ctor { #(#names),* }),
+        Fields::Unnamed(_) => quote!(#ctor ( #(#names),* )),
+        Fields::Unit => quote!(#ctor),
+    };
+
+    Ok(quote! {{
+        #(#bindings)*
+        #(#assumptions)*
+        #construct
+    }})
+}
+
+/// Parse the `#[verifier(assume = "...")]` attributes of a field
+fn field_assumptions(f: &syn::Field) -> syn::Result<Vec<Expr>> {
+    let mut r = Vec::new();
+    for attr in &f.attrs {
+        if !attr.path.is_ident("verifier") {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            m => return Err(syn::Error::new(m.span(), "expected #[verifier(assume = \"...\")]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("assume") => match &nv.lit {
+                    Lit::Str(s) => r.push(s.parse::<Expr>()?),
+                    lit => return Err(syn::Error::new(lit.span(), "expected a string")),
+                },
+                m => return Err(syn::Error::new(m.span(), "unknown verifier attribute")),
+            }
+        }
+    }
+    Ok(r)
+}
+
+/// Parse the `#[verifier(crate = "...")]` attribute of a struct or enum
+fn crate_path(attrs: &[syn::Attribute]) -> syn::Result<syn::Path> {
+    let mut krate = default_crate();
+    for attr in attrs {
+        if !attr.path.is_ident("verifier") {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            m => return Err(syn::Error::new(m.span(), "expected #[verifier(crate = \"...\")]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => match &nv.lit {
+                    Lit::Str(s) => krate = parse_crate(s)?,
+                    lit => return Err(syn::Error::new(lit.span(), "expected a string")),
+                },
+                m => return Err(syn::Error::new(m.span(), "unknown verifier attribute")),
+            }
+        }
+    }
+    Ok(krate)
+}
+
+/// Parse the path given by `crate = "path"`
+fn parse_crate(s: &LitStr) -> syn::Result<syn::Path> {
+    s.parse_with(syn::Path::parse_mod_style)
+}
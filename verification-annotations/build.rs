@@ -1,5 +1,6 @@
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(seahorn_replay)");
     #[cfg(feature = "verifier-seahorn")]
     seahorn();
 }
@@ -7,7 +8,31 @@ fn main() {
 #[cfg(feature = "verifier-seahorn")]
 fn seahorn() {
     println!("cargo:rerun-if-changed=lib/seahorn.c");
-    cc::Build::new()
-        .file("lib/seahorn.c")
-        .compile("seahorn");
+    println!("cargo:rerun-if-env-changed=SEAHORN_CEX_HARNESS");
+
+    // When replaying a SeaHorn counterexample, the harness generated
+    // by SeaHorn (using 'sea ... --cex=harness.ll') provides the
+    // __VERIFIER_nondet_* functions instead of our stubs.
+    match std::env::var_os("SEAHORN_CEX_HARNESS") {
+        Some(harness) => {
+            println!("cargo:rerun-if-changed={}", harness.to_string_lossy());
+            println!("cargo:rustc-cfg=seahorn_replay");
+            cc::Build::new()
+                .file("lib/seahorn.c")
+                .define("SEAHORN_REPLAY", None)
+                .compile("seahorn");
+            let mut build = cc::Build::new();
+            // LLVM harnesses can only be compiled by clang
+            let path = std::path::Path::new(&harness);
+            if matches!(path.extension().and_then(|e| e.to_str()), Some("ll") | Some("bc")) {
+                build.compiler("clang");
+            }
+            build.file(path).compile("seahorn_cex");
+        }
+        None => {
+            cc::Build::new()
+                .file("lib/seahorn.c")
+                .compile("seahorn");
+        }
+    }
 }
@@ -14,6 +14,10 @@ void __VERIFIER_assume(int pred) {
   }
 }
 
+// When replaying a counterexample, the non-deterministic values
+// are provided by the harness generated by SeaHorn.
+#ifndef SEAHORN_REPLAY
+
 uint8_t   __VERIFIER_nondet_u8()    { return 0; }
 uint16_t  __VERIFIER_nondet_u16()   { return 0; }
 uint32_t  __VERIFIER_nondet_u32()   { return 0; }
@@ -28,3 +32,5 @@ intptr_t  __VERIFIER_nondet_isize() { return 0; }
 
 float     __VERIFIER_nondet_f32()   { return 0; }
 double    __VERIFIER_nondet_f64()   { return 0; }
+
+#endif // SEAHORN_REPLAY
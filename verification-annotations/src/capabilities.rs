@@ -0,0 +1,60 @@
+// Copyright 2021 The Propverify authors
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/////////////////////////////////////////////////////////////////
+// Backend capabilities
+//
+// Each backend describes itself using a `Capabilities` value
+// (returned by `verifier::capabilities()`) so that harnesses can
+// adapt to the backend using ordinary `if` statements instead of
+// `#[cfg(...)]` attributes on the backend features.
+//
+//     if verifier::capabilities().replay && verifier::is_replay() {
+//         println!("x = {}", x);
+//     }
+//
+// Note that a harness must still use `#[cfg(...)]` around code
+// that only exists for some backends
+// (e.g., the `crucible` crate used by Crux-MIR).
+/////////////////////////////////////////////////////////////////
+
+/// The verification backends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Klee,
+    Crux,
+    Seahorn,
+    Replay,
+}
+
+/// What a backend supports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The active backend
+    pub backend: Backend,
+
+    /// Counterexamples can be replayed with this backend
+    /// (and `is_replay()` may return `true`).
+    pub replay: bool,
+
+    /// Expected failures (`expect` and `expect_raw`) are checked.
+    pub expect: bool,
+
+    /// Non-deterministic `f32` and `f64` values can be created.
+    pub floats: bool,
+
+    /// Non-deterministic `u128` and `i128` values can be created.
+    pub wide_ints: bool,
+
+    /// Memory can be allocated with a symbolic size.
+    pub symbolic_sizes: bool,
+}
+
+/////////////////////////////////////////////////////////////////
+// End
+/////////////////////////////////////////////////////////////////
@@ -10,30 +10,84 @@
 // FFI wrapper for Crux-mir static simulator tool
 /////////////////////////////////////////////////////////////////
 
+use std::cell::RefCell;
+use std::fmt::Display;
+
+use crate::capabilities::{Backend, Capabilities};
 use crate::traits::*;
 
-impl <T: AbstractValue> VerifierNonDet for T {
-    fn verifier_nondet(self) -> Self {
-        T::abstract_value()
-    }
+macro_rules! make_symbolic {
+    ($($typ:ty),*) => {
+        $(
+            impl VerifierNonDet for $typ {
+                fn verifier_nondet(self) -> Self {
+                    Self::abstract_value()
+                }
+            }
+
+            impl AbstractValue for $typ {
+                fn abstract_value() -> Self {
+                    // We assume the string argument is just for reporting to the user, and
+                    // doesn't affect the results.
+                    let r : $typ = crucible::Symbolic::symbolic("");
+                    record("", r)
+                }
+            }
+
+            impl Symbolic for $typ {
+                fn symbolic(desc: &'static str) -> Self {
+                    let r : $typ = crucible::Symbolic::symbolic(desc);
+                    record(desc, r)
+                }
+            }
+        )*
+    };
 }
 
-impl <T: crucible::Symbolic> AbstractValue for T {
-    fn abstract_value() -> Self {
-        // We assume the string argument is just for reporting to the user, and
-        // doesn't affect the results.
-        let r : T = crucible::Symbolic::symbolic("");
-        r
-    }
+make_symbolic!(u8, u16, u32, u64, u128, usize);
+make_symbolic!(i8, i16, i32, i64, i128, isize);
+make_symbolic!(f32, f64);
+make_symbolic!(bool);
+
+// Crux-MIR does not support replay so, to let failures be replayed
+// with the `verifier-replay` backend, we record every symbolic value
+// and print a concrete instance of each value when a failure occurs.
+type Concretizer = Box<dyn Fn() -> String>;
+
+thread_local! {
+    // (The Crux toolchain, nightly-2020-03-22, predates `const` initializers.)
+    #[allow(clippy::missing_const_for_thread_local)]
+    static VALUES: RefCell<Vec<(&'static str, Concretizer)>> = RefCell::new(Vec::new());
 }
 
-impl <T: crucible::Symbolic + Default> Symbolic for T {
-    fn symbolic(desc: &'static str) -> Self {
-        let r : T = crucible::Symbolic::symbolic(desc);
-        r
-    }
+fn record<T: Display + Copy + 'static>(desc: &'static str, x: T) -> T {
+    let concretize: Concretizer = Box::new(move || crucible::concretize(x).to_string());
+    VALUES.with(|vs| vs.borrow_mut().push((desc, concretize)));
+    x
+}
+
+/// Print the values of all symbolic values in a form that can be
+/// used as a `VERIFIER_REPLAY_FILE`.
+fn print_counterexample() {
+    VALUES.with(|vs| {
+        for (desc, concretize) in vs.borrow().iter() {
+            let desc = if desc.is_empty() { "_" } else { desc };
+            eprintln!("VERIFIER_VALUE: {} = {}", desc, concretize());
+        }
+    })
+}
+
+/// Give the name `desc` to all unnamed symbolic values created by `f`.
+///
+/// Crux-MIR does not use the names of values so this just calls `f`.
+pub fn with_name<R>(_desc: &'static str, f: impl FnOnce() -> R) -> R {
+    f()
 }
 
+/// Create a vector of `n` bytes with symbolic contents
+pub fn verifier_nondet_bytes(n: usize) -> Vec<u8> {
+    (0..n).map(|_| u8::abstract_value()).collect()
+}
 
 /// Assume that condition `cond` is true
 ///
@@ -42,11 +96,28 @@ pub fn assume(cond: bool) {
     crucible::crucible_assume!(cond)
 }
 
+/// Describe what the Crux-MIR backend supports
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        backend: Backend::Crux,
+        // Counterexamples are replayed using the replay backend
+        replay: false,
+        expect: true,
+        floats: true,
+        wide_ints: true,
+        symbolic_sizes: false,
+    }
+}
+
 /// Reject the current execution with a verification failure.
 ///
 /// In almost all circumstances, `report_error` should
 /// be used instead because it generates an error message.
 pub fn abort() {
+    if is_expected(None) {
+        reject()
+    }
+    print_counterexample();
     crucible::crucible_assert!(false)
 }
 
@@ -61,53 +132,146 @@ pub fn reject() -> ! {
     panic!("should have been rejected!");
 }
 
-/// Detect whether the program is being run symbolically in KLEE
-/// or being replayed using the kleeRuntest runtime.
+/// Detect whether the program is being replayed.
 ///
-/// This is used to decide whether to display the values of
-/// variables that may be either symbolic or concrete.
+/// Crux-MIR runs are never replays: counterexamples are replayed
+/// using the `verifier-replay` backend instead.
 pub fn is_replay() -> bool {
-    panic!("crux doesn't support replay")
+    false
 }
 
 /// Reject the current execution with a verification failure
 /// and an error message.
 pub fn report_error(message: &str) {
+    if is_expected(Some(message)) {
+        reject()
+    }
+    print_counterexample();
     crucible::crucible_assert!(false, "VERIFIER: ERROR: {}", message);
 }
 
+/// Report a failing assertion (used by the `assert!` macros)
+#[doc(hidden)]
+pub fn report_panic(message: &str) {
+    if is_expected(Some(message)) {
+        reject()
+    }
+    print_counterexample();
+    crucible::crucible_assert!(false, "VERIFIER: panicked at '{}'", message);
+}
+
+// Crux-MIR does not have a driver script that scans the output for
+// expected failures so, instead, we record the expectation and
+// check failures against it in the harness.
+//
+// The outer `Option` records whether failure is expected and the
+// inner `Option` holds the expected message (if any).
+thread_local! {
+    #[allow(clippy::missing_const_for_thread_local)]
+    static EXPECT: RefCell<Option<Option<String>>> = RefCell::new(None);
+}
+
+/// Test whether a failure with message `msg` matches the expected failure.
+fn is_expected(msg: Option<&str>) -> bool {
+    EXPECT.with(|e| match (&*e.borrow(), msg) {
+        (None, _) => false,
+        (Some(None), _) => true,
+        (Some(Some(expected)), Some(msg)) => msg.contains(expected.as_str()),
+        (Some(Some(_)), None) => false,
+    })
+}
+
 /// Declare that failure is the expected behaviour
-pub fn expect_raw(_msg: &str) {
-    panic!("not implemented")
+///
+/// The argument is an attribute such as `should_panic` or
+/// `should_panic(expected = "message")`.
+pub fn expect_raw(msg: &str) {
+    let msg = msg.trim();
+    if msg == "should_panic" {
+        expect(None)
+    } else if let (Some(start), Some(end)) = (msg.find('"'), msg.rfind('"')) {
+        if start < end {
+            expect(Some(&msg[start + 1..end]))
+        } else {
+            expect(None)
+        }
+    } else {
+        expect(None)
+    }
 }
 
 /// Declare that failure is the expected behaviour
-pub fn expect(_msg: Option<&str>) {
-    panic!("not implemented")
+///
+/// Failures that match the expectation are treated as successful paths.
+/// Since Crux-MIR checks every path independently, paths that do not fail
+/// are also accepted and failures that are built into Crux-MIR
+/// (such as arithmetic overflow) cannot be matched.
+pub fn expect(msg: Option<&str>) {
+    EXPECT.with(|e| *e.borrow_mut() = Some(msg.map(String::from)))
 }
 
 
 #[macro_export]
 macro_rules! assert {
-    ($cond:expr) => {
-        $crate::crucible::crucible_assert!($cond, "VERIFIER: assertion failed: {}", stringify!($cond));
-    };
-    // ($cond:expr,) => { ... };
-    // ($cond:expr, $($arg:tt)+) => { ... };
+    ($cond:expr,) => { $crate::assert!($cond) };
+    ($cond:expr) => { $crate::assert!($cond, "assertion failed: {}", stringify!($cond)) };
+    ($cond:expr, $($arg:tt)+) => {{
+        // The message is only formatted on failing paths
+        if ! $cond {
+            let message = format!($($arg)+);
+            $crate::report_panic(&message);
+        }
+    }}
 }
 
 #[macro_export]
 macro_rules! assert_eq {
-    ($left:expr, $right:expr) => { $crate::assert!(($left) == ($right)); };
-    // ($left:expr, $right:expr,) => { ... };
-    // ($left:expr, $right:expr, $($arg:tt)+) => { ... };
+    ($left:expr, $right:expr,) => { $crate::assert_eq!($left, $right) };
+    ($left:expr, $right:expr) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left == right,
+            "assertion failed: `(left == right)` \
+             \n  left: `{:?}`,\n right: `{:?}`",
+            left,
+            right)
+    }};
+    ($left:expr, $right:expr, $fmt:tt $($arg:tt)*) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left == right,
+            concat!(
+                "assertion failed: `(left == right)` \
+                 \n  left: `{:?}`, \n right: `{:?}`: ", $fmt),
+            left, right $($arg)*);
+    }};
 }
 
 #[macro_export]
 macro_rules! assert_ne {
-    ($left:expr, $right:expr) => { $crate::assert!(($left) != ($right)); };
-    // ($left:expr, $right:expr,) => { ... };
-    // ($left:expr, $right:expr, $($arg:tt)+) => { ... };
+    ($left:expr, $right:expr,) => { $crate::assert_ne!($left, $right) };
+    ($left:expr, $right:expr) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left != right,
+            "assertion failed: `(left != right)` \
+             \n  left: `{:?}`,\n right: `{:?}`",
+            left,
+            right)
+    }};
+    ($left:expr, $right:expr, $fmt:tt $($arg:tt)*) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left != right,
+            concat!(
+                "assertion failed: `(left != right)` \
+                 \n  left: `{:?}`, \n right: `{:?}`: ", $fmt),
+            left, right $($arg)*);
+    }};
 }
 
 /////////////////////////////////////////////////////////////////
@@ -0,0 +1,79 @@
+// Copyright 2021 The Propverify authors
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/////////////////////////////////////////////////////////////////
+// Symbolic environment variables
+//
+// Unlike stdin and files, environment variables can be made symbolic
+// in place: we store the symbolic value in the real process environment
+// so that code under test can keep using `std::env::var`.
+/////////////////////////////////////////////////////////////////
+
+use std::ffi::OsString;
+
+use crate::{assume, AbstractValue};
+
+/// Make the environment variables `names` symbolic.
+///
+/// Each variable is either absent or present with a value of
+/// at most `max_len` bytes.
+///
+/// With KLEE, the contents of the variables are symbolic.
+/// Other backends cannot store symbolic values in the environment
+/// so they enumerate concrete values: absence and one value of each
+/// possible length.
+///
+/// When replaying a test, the chosen values are printed.
+pub fn symbolic_env(names: &[&str], max_len: usize) {
+    for name in names {
+        let value = symbolic_var(max_len);
+
+        if crate::is_replay() {
+            eprintln!("  Env {} = {:?}", name, value);
+        }
+
+        match value {
+            Some(value) => std::env::set_var(name, value),
+            None => std::env::remove_var(name),
+        }
+    }
+}
+
+#[cfg(feature = "verifier-klee")]
+fn symbolic_var(max_len: usize) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+
+    let present: bool = AbstractValue::abstract_value();
+    if !present {
+        return None;
+    }
+    let value = crate::io::symbolic_contents(max_len);
+    // The environment is stored as C strings
+    for b in &value {
+        assume(*b != 0);
+    }
+    Some(OsString::from_vec(value))
+}
+
+#[cfg(not(feature = "verifier-klee"))]
+fn symbolic_var(max_len: usize) -> Option<OsString> {
+    // 0 => absent, n+1 => a value of length n
+    let choice: usize = AbstractValue::abstract_value();
+    assume(choice <= max_len.saturating_add(1));
+    // Branch on each possible choice so that every value is concrete
+    for len in 0..=max_len {
+        if choice == len + 1 {
+            return Some("x".repeat(len).into());
+        }
+    }
+    None
+}
+
+/////////////////////////////////////////////////////////////////
+// End
+/////////////////////////////////////////////////////////////////
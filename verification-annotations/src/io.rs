@@ -0,0 +1,254 @@
+// Copyright 2021 The Propverify authors
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/////////////////////////////////////////////////////////////////
+// Symbolic models of stdin and files
+//
+// Verifiers cannot reason about the contents of real files
+// so these models replace stdin and named files with in-memory
+// buffers of bounded, symbolic size and symbolic contents.
+//
+// A harness first declares the inputs that it wants to be symbolic
+//
+//     verifier::io::symbolic_stdin(16);
+//     verifier::io::symbolic_file("config.txt", 32);
+//
+// and the code under test then reads them using `verifier::io::stdin()`
+// and `verifier::io::File::open(...)` in place of the `std` versions.
+// A convenient way to do this is to select the imports using `cfg`
+//
+//     #[cfg(verify)] use verification_annotations::io::{stdin, File};
+//     #[cfg(not(verify))] use std::{io::stdin, fs::File};
+/////////////////////////////////////////////////////////////////
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::{assume, verifier_nondet_bytes, AbstractValue};
+
+/// Contents and read position shared by all handles to stdin
+type SharedCursor = Rc<RefCell<Cursor<Vec<u8>>>>;
+
+thread_local! {
+    // (`const` initializers need Rust 1.59.)
+    #[allow(clippy::missing_const_for_thread_local)]
+    static STDIN: RefCell<Option<SharedCursor>> = RefCell::new(None);
+    static FILES: RefCell<HashMap<PathBuf, Rc<Vec<u8>>>> = RefCell::new(HashMap::new());
+}
+
+/// Create a buffer of up to `max_len` bytes where both the length
+/// and the contents of the buffer are symbolic.
+pub(crate) fn symbolic_contents(max_len: usize) -> Vec<u8> {
+    // The buffer is created at full size and then truncated because
+    // allocating a buffer of symbolic size causes KLEE to complain.
+    let mut data = verifier_nondet_bytes(max_len);
+    let len: usize = AbstractValue::abstract_value();
+    assume(len <= max_len);
+    data.truncate(len);
+    data
+}
+
+/// Make stdin symbolic with a length of at most `max_len` bytes.
+///
+/// Any data that was previously available on the symbolic stdin
+/// is discarded.
+pub fn symbolic_stdin(max_len: usize) {
+    let contents = Cursor::new(symbolic_contents(max_len));
+    STDIN.with(|s| *s.borrow_mut() = Some(Rc::new(RefCell::new(contents))));
+}
+
+/// Create a symbolic file called `path` with a length of at most `max_len` bytes.
+///
+/// Declaring the same file twice replaces the contents of the file.
+pub fn symbolic_file<P: AsRef<Path>>(path: P, max_len: usize) {
+    let contents = Rc::new(symbolic_contents(max_len));
+    FILES.with(|fs| fs.borrow_mut().insert(path.as_ref().to_path_buf(), contents));
+}
+
+/// A handle to the symbolic stdin.
+///
+/// Like `std::io::Stdin`, all handles share the same read position.
+/// If `symbolic_stdin` has not been called, stdin is empty.
+pub struct Stdin {
+    inner: Option<SharedCursor>,
+}
+
+/// Construct a handle to the symbolic stdin.
+pub fn stdin() -> Stdin {
+    Stdin { inner: STDIN.with(|s| s.borrow().clone()) }
+}
+
+impl Stdin {
+    /// Read a line of input into `buf` (compatible with `std::io::Stdin::read_line`).
+    pub fn read_line(&self, buf: &mut String) -> io::Result<usize> {
+        match &self.inner {
+            Some(c) => c.borrow_mut().read_line(buf),
+            None => Ok(0),
+        }
+    }
+
+    /// Lock this handle for buffered reading (compatible with `std::io::Stdin::lock`).
+    pub fn lock(&self) -> StdinLock {
+        StdinLock {
+            inner: self.inner.clone(),
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for Stdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &self.inner {
+            Some(c) => c.borrow_mut().read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+/// A locked handle to the symbolic stdin that implements `BufRead`.
+///
+/// The lock buffers the rest of stdin and, when it is dropped,
+/// returns any data that it did not consume so that it can be
+/// read by other handles.
+/// (Other handles should not be used while the lock exists.)
+pub struct StdinLock {
+    inner: Option<SharedCursor>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for StdinLock {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = std::cmp::min(buf.len(), available.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for StdinLock {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+            if let Some(c) = &self.inner {
+                c.borrow_mut().read_to_end(&mut self.buf)?;
+            }
+        }
+        Ok(&self.buf[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = std::cmp::min(self.pos + amt, self.buf.len());
+    }
+}
+
+impl Drop for StdinLock {
+    fn drop(&mut self) {
+        let unread = self.buf.len() - self.pos;
+        if let (Some(c), true) = (&self.inner, unread > 0) {
+            let mut c = c.borrow_mut();
+            let pos = c.position() - unread as u64;
+            c.set_position(pos);
+        }
+    }
+}
+
+/// A symbolic file opened for reading.
+///
+/// Each call to `File::open` returns a fresh handle positioned at
+/// the start of the file.
+pub struct File {
+    data: Rc<Vec<u8>>,
+    pos: usize,
+}
+
+impl File {
+    /// Open a file that was declared using `symbolic_file`.
+    ///
+    /// Opening any other file fails with `ErrorKind::NotFound`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+        match FILES.with(|fs| fs.borrow().get(path.as_ref()).cloned()) {
+            Some(data) => Ok(File { data, pos: 0 }),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no symbolic file '{}'", path.as_ref().display()),
+            )),
+        }
+    }
+
+    /// The length of the file in bytes.
+    pub fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Test whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn remaining(&self) -> &[u8] {
+        let pos = std::cmp::min(self.pos, self.data.len());
+        &self.data[pos..]
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.remaining();
+        let n = std::cmp::min(buf.len(), remaining.len());
+        buf[..n].copy_from_slice(&remaining[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl BufRead for File {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => {
+                self.pos = n as usize;
+                return Ok(n);
+            }
+            SeekFrom::End(n) => (self.data.len() as i64, n),
+            SeekFrom::Current(n) => (self.pos as i64, n),
+        };
+        match base.checked_add(offset) {
+            Some(n) if n >= 0 => {
+                self.pos = n as usize;
+                Ok(n as u64)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////
+// End
+/////////////////////////////////////////////////////////////////
@@ -11,8 +11,12 @@
 /////////////////////////////////////////////////////////////////
 
 pub use crate::traits::*;
+use crate::capabilities::{Backend, Capabilities};
 
+use core::panic::PanicInfo;
+use std::cell::Cell;
 use std::default::Default;
+use std::ffi::CString;
 use std::os::raw;
 
 #[link(name = "kleeRuntest")]
@@ -24,20 +28,57 @@ extern "C" {
     fn klee_is_replay() -> i32;
 }
 
+thread_local! {
+    #[allow(clippy::missing_const_for_thread_local)]
+    static NAME: Cell<Option<&'static str>> = Cell::new(None);
+}
+
+/// Give the name `desc` to all unnamed symbolic values created by `f`.
+///
+/// KLEE records these names in `.ktest` files so this makes
+/// counterexamples traceable to harness variables.
+/// (`Symbolic::symbolic(desc)` names its value directly.)
+pub fn with_name<R>(desc: &'static str, f: impl FnOnce() -> R) -> R {
+    let old = NAME.with(|n| n.replace(Some(desc)));
+    let r = f();
+    NAME.with(|n| n.set(old));
+    r
+}
+
+/// Make `length` bytes at `data` symbolic.
+///
+/// The object is named `desc` or, if that is empty, the name
+/// set by `with_name` (if any).
+fn make_symbolic(data: *mut raw::c_void, length: usize, desc: &str) {
+    let desc = if desc.is_empty() { NAME.with(|n| n.get()) } else { Some(desc) };
+    // KLEE copies the name so it only needs to live until the call returns
+    let name = desc.and_then(|d| CString::new(d).ok());
+    let name = name.as_ref().map_or(std::ptr::null(), |n| n.as_ptr());
+    unsafe { klee_make_symbolic(data, length, name) }
+}
+
 /// Create instance for any type consisting of contiguous memory
 /// where all bit-patterns are legal values of the type.
 macro_rules! make_verifier_nondet {
     ($typ:ident) => {
         impl VerifierNonDet for $typ {
             fn verifier_nondet(self) -> Self {
-                let mut r = self;
-                unsafe {
-                    let data = std::mem::transmute(&mut r);
-                    let length = std::mem::size_of::<$typ>();
-                    let null = 0 as *const i8;
-                    klee_make_symbolic(data, length, null)
-                }
-                return r;
+                Self::symbolic("")
+            }
+        }
+
+        impl AbstractValue for $typ {
+            fn abstract_value() -> Self {
+                Self::symbolic("")
+            }
+        }
+
+        impl Symbolic for $typ {
+            fn symbolic(desc: &'static str) -> Self {
+                let mut r = Self::default();
+                let data = &mut r as *mut $typ as *mut raw::c_void;
+                make_symbolic(data, std::mem::size_of::<$typ>(), desc);
+                r
             }
         }
     };
@@ -60,23 +101,32 @@ make_verifier_nondet!(isize);
 make_verifier_nondet!(f32);
 make_verifier_nondet!(f64);
 
+/// Create a vector of `n` bytes with symbolic contents
+pub fn verifier_nondet_bytes(n: usize) -> Vec<u8> {
+    let mut r = vec![0u8; n];
+    if n > 0 {
+        make_symbolic(r.as_mut_ptr() as *mut raw::c_void, n, "")
+    }
+    r
+}
+
 impl VerifierNonDet for bool {
     fn verifier_nondet(self) -> Self {
-        let c = VerifierNonDet::verifier_nondet(0u8);
-        assume(c == 0 || c == 1);
-        c == 1
+        Self::symbolic("")
     }
 }
 
-impl <T: VerifierNonDet + Default> AbstractValue for T {
+impl AbstractValue for bool {
     fn abstract_value() -> Self {
-        Self::verifier_nondet(Self::default())
+        Self::symbolic("")
     }
 }
 
-impl <T: VerifierNonDet + Default> Symbolic for T {
-    fn symbolic(_desc: &'static str) -> Self {
-        Self::verifier_nondet(Self::default())
+impl Symbolic for bool {
+    fn symbolic(desc: &'static str) -> Self {
+        let c = u8::symbolic(desc);
+        assume(c == 0 || c == 1);
+        c == 1
     }
 }
 
@@ -95,6 +145,30 @@ pub fn abort() -> ! {
     unsafe { klee_abort() }
 }
 
+/// Report a panic as a verification failure.
+///
+/// `rvt-patch-llvm --panic-handler klee_panic` redirects panics and
+/// aborts to this function (`info` is `None` for aborts) so that KLEE
+/// does not need to support unwinding.
+#[no_mangle]
+fn klee_panic(info: Option<&PanicInfo>) -> ! {
+    if let Some(info) = info {
+        // Use the format of assert! so that expected failures are recognized.
+        // PanicInfo is displayed as "panicked at 'message', location" by
+        // older versions of Rust and as "panicked at location:\nmessage" by
+        // newer versions (which are converted to the older format).
+        let text = info.to_string();
+        let prefix = "panicked at ";
+        match text.find(":\n") {
+            Some(i) if text.starts_with(prefix) && !text.starts_with("panicked at '") => {
+                eprintln!("VERIFIER: panicked at '{}', {}", &text[i + 2..], &text[prefix.len()..i])
+            }
+            _ => eprintln!("VERIFIER: {}", text),
+        }
+    }
+    abort();
+}
+
 /// Reject the current execution path with a verification success.
 /// This is equivalent to `assume(false)`
 /// and the opposite of `report_error(...)`.
@@ -114,6 +188,19 @@ pub fn is_replay() -> bool {
     unsafe { klee_is_replay() != 0 }
 }
 
+/// Describe what the KLEE backend supports
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        backend: Backend::Klee,
+        replay: true,
+        expect: true,
+        floats: true,
+        wide_ints: true,
+        // KLEE concretizes the size of allocations
+        symbolic_sizes: false,
+    }
+}
+
 /// Reject the current execution with a verification failure
 /// and an error message.
 pub fn report_error(message: &str) -> ! {
@@ -155,6 +242,7 @@ macro_rules! assert {
 
 #[macro_export]
 macro_rules! assert_eq {
+    ($left:expr, $right:expr,) => { $crate::assert_eq!($left, $right) };
     ($left:expr, $right:expr) => {{
         let left = $left;
         let right = $right;
@@ -179,6 +267,7 @@ macro_rules! assert_eq {
 
 #[macro_export]
 macro_rules! assert_ne {
+    ($left:expr, $right:expr,) => { $crate::assert_ne!($left, $right) };
     ($left:expr, $right:expr) => {{
         let left = $left;
         let right = $right;
@@ -7,9 +7,46 @@
 // except according to those terms.
 
 // Traits for creating symbolic/abstract values
+#[macro_use]
 mod traits;
 pub use crate::traits::*;
 
+// Derive macros for the traits, function contracts and stubs
+// (The generated code refers to `::verification_annotations` so
+// we make that name available within this crate too.)
+extern crate self as verification_annotations;
+pub use verification_annotations_derive::{ensures, requires, stub, AbstractValue, Symbolic};
+
+// Contracts expand into two versions of each function and
+// the `contracts` feature selects which version is used:
+// the function itself or a replacement based on its contract.
+#[cfg(not(feature = "contracts"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_contract {
+    (checked { $($checked:tt)* } replaced { $($replaced:tt)* }) => { $($checked)* };
+}
+
+#[cfg(feature = "contracts")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_contract {
+    (checked { $($checked:tt)* } replaced { $($replaced:tt)* }) => { $($replaced)* };
+}
+
+// The derived `Symbolic` implementations describe each part of a value
+// (e.g., the field `lo` of `range` is described as `range.lo`).
+// The description is leaked because `Symbolic::symbolic` needs a
+// `&'static str`.
+#[doc(hidden)]
+pub fn __describe(desc: &str, part: &str) -> &'static str {
+    Box::leak(format!("{}.{}", desc, part).into_boxed_str())
+}
+
+// Description of what the selected backend supports
+mod capabilities;
+pub use crate::capabilities::{Backend, Capabilities};
+
 #[cfg(feature = "verifier-klee")]
 mod klee;
 #[cfg(feature = "verifier-klee")]
@@ -27,6 +64,24 @@ mod seahorn;
 #[cfg(feature = "verifier-seahorn")]
 pub use crate::seahorn::*;
 
+#[cfg(feature = "verifier-replay")]
+mod replay;
+#[cfg(feature = "verifier-replay")]
+pub use crate::replay::*;
+
+// Symbolic models of stdin and files
+pub mod io;
+
+// Quantified properties
+mod quantifiers;
+pub use crate::quantifiers::{assert_forall, assume_exists};
+#[cfg(feature = "verifier-seahorn")]
+pub use crate::quantifiers::{assert_exists, assume_forall};
+
+// Symbolic environment variables
+mod env;
+pub use crate::env::symbolic_env;
+
 #[macro_export]
 macro_rules! verifier_assert {
     ($cond:expr) => { $crate::assert!($cond); };
@@ -42,6 +97,82 @@ macro_rules! verifier_unreachable {
     () => { $crate::assert!(false, "unreachable assertion was reached"); };
 }
 
+/// Check a loop invariant.
+///
+/// This should be placed at the start of the loop body and
+/// is checked on every iteration that reaches it.
+/// SeaHorn is also given the invariant as a hint.
+///
+/// ```ignore
+/// while i < n {
+///     verifier::invariant!(i <= n);
+///     ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! invariant {
+    ($cond:expr) => {{
+        let cond = $cond;
+        $crate::assert!(cond, "loop invariant failed: {}", stringify!($cond));
+        $crate::__invariant_hint!(cond);
+    }};
+}
+
+// SeaHorn infers loop invariants so, once an invariant has been checked,
+// it is assumed to give SeaHorn a candidate invariant.
+// (Path-based backends have no use for hints.)
+#[cfg(feature = "verifier-seahorn")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __invariant_hint {
+    ($cond:expr) => { $crate::assume($cond) };
+}
+
+#[cfg(not(feature = "verifier-seahorn"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __invariant_hint {
+    ($cond:expr) => { () };
+}
+
+/// Check that a loop variant decreases on every iteration.
+///
+/// This should be placed at the start of the loop body.
+/// The variant must be an integer and is checked to be non-negative
+/// so that checking that it decreases is enough to show termination.
+///
+/// The value of the variant on the previous iteration is kept in
+/// an `Option` (initially `None`) that is declared before the loop
+/// so that the check starts again each time that the loop is run.
+///
+/// ```ignore
+/// let mut variant = None;
+/// while i < n {
+///     verifier::decreases!(variant, n - i);
+///     ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! decreases {
+    ($state:ident, $measure:expr) => {{
+        let measure = $measure;
+        $crate::assert!(
+            $crate::__is_non_negative(&measure),
+            "loop variant is negative: {}",
+            stringify!($measure)
+        );
+        if let Some(previous) = &$state {
+            $crate::assert!(measure < *previous, "loop variant did not decrease: {}", stringify!($measure));
+        }
+        $state = Some(measure);
+    }};
+}
+
+#[doc(hidden)]
+pub fn __is_non_negative<T: PartialOrd + Default>(x: &T) -> bool {
+    *x >= T::default()
+}
+
 // At the moment, the cargo-verify script does not support
 // use of a separate test directory so, for now, we put
 // the tests here.
@@ -0,0 +1,117 @@
+// Copyright 2021 The Propverify authors
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/////////////////////////////////////////////////////////////////
+// Quantified properties over ranges
+//
+// Neither helper loops over the range: each one creates a single
+// symbolic index in the range and checks or assumes the property
+// at that index. This makes them cheap, but the encoding is only
+// correct in one polarity so each helper does the assertion or
+// assumption itself
+//
+// - `assert_forall` asserts the property at the chosen index
+//
+//       verifier::assert_forall(0..xs.len(), |i| xs[i] < 10);
+//
+//   The assertion must hold for every choice of index so the
+//   verifier checks the property at every index in the range.
+//
+// - `assume_exists` assumes the property at the chosen index
+//
+//       verifier::assume_exists(0..xs.len(), |i| xs[i] == 0);
+//
+//   The assumption only keeps the paths where the chosen index
+//   is a witness so the property holds for some index.
+//
+// The opposite polarities (assuming a property of every index or
+// asserting that some index has a property) cannot be expressed with
+// a single symbolic index.
+//
+// SeaHorn does not unroll loops: it infers loop invariants and
+// so it reasons about every iteration of a loop at once.
+// With SeaHorn, the opposite polarities are therefore provided by
+// `assume_forall` and `assert_exists` which are lowered to loops over
+// the range. (With the path-based backends, these loops would
+// be explored one path per iteration so they are not provided:
+// use an ordinary loop over a bounded range instead.)
+/////////////////////////////////////////////////////////////////
+
+use std::ops::Range;
+
+use crate::{assume, Symbolic};
+
+/// Choose a symbolic index in `range`.
+///
+/// If the range is empty, there is no index and the path is rejected.
+fn choose<T>(desc: &'static str, range: &Range<T>) -> T
+where
+    T: Symbolic + PartialOrd,
+{
+    let i = T::symbolic(desc);
+    assume(range.contains(&i));
+    i
+}
+
+/// Assert that property `p` holds for every index in `range`.
+///
+/// An empty range satisfies every property.
+pub fn assert_forall<T, F>(range: Range<T>, p: F)
+where
+    T: Symbolic + PartialOrd,
+    F: FnOnce(T) -> bool,
+{
+    // (Rejecting the path would skip any later checks.)
+    if range.start >= range.end {
+        return;
+    }
+    let i = choose("forall", &range);
+    crate::assert!(p(i), "property does not hold for every index in the range");
+}
+
+/// Assume that property `p` holds for some index in `range`.
+///
+/// No index in an empty range satisfies a property so
+/// an empty range rejects the current path.
+pub fn assume_exists<T, F>(range: Range<T>, p: F)
+where
+    T: Symbolic + PartialOrd,
+    F: FnOnce(T) -> bool,
+{
+    let i = choose("exists", &range);
+    assume(p(i));
+}
+
+/// Assume that property `p` holds for every index in `range`
+/// (SeaHorn only).
+#[cfg(feature = "verifier-seahorn")]
+pub fn assume_forall<T, F>(mut range: Range<T>, p: F)
+where
+    Range<T>: Iterator<Item = T>,
+    F: FnMut(T) -> bool,
+{
+    assume(range.all(p));
+}
+
+/// Assert that property `p` holds for some index in `range`
+/// (SeaHorn only).
+///
+/// No index in an empty range satisfies a property so
+/// the assertion fails if the range is empty.
+#[cfg(feature = "verifier-seahorn")]
+pub fn assert_exists<T, F>(mut range: Range<T>, p: F)
+where
+    Range<T>: Iterator<Item = T>,
+    F: FnMut(T) -> bool,
+{
+    crate::assert!(range.any(p), "property does not hold for any index in the range");
+}
+
+/////////////////////////////////////////////////////////////////
+// End
+/////////////////////////////////////////////////////////////////
@@ -0,0 +1,253 @@
+// Copyright 2021 The Propverify authors
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/////////////////////////////////////////////////////////////////
+// Concrete replay of counterexamples
+//
+// This backend runs a harness natively using concrete values
+// read from a file (named by the environment variable
+// VERIFIER_REPLAY_FILE) instead of symbolic values.
+// Each line of the file has the form
+//
+//     <description> = <value>
+//
+// and values are consumed in the order that the harness creates
+// symbolic values.
+//
+// This is used to replay counterexamples found by verifiers that
+// do not have their own replay mechanism (e.g., Crux-MIR prints
+// "VERIFIER_VALUE: <description> = <value>" lines when it finds a
+// failure).
+/////////////////////////////////////////////////////////////////
+
+pub use crate::traits::*;
+use crate::capabilities::{Backend, Capabilities};
+
+use std::cell::RefCell;
+use std::default::Default;
+use std::str::FromStr;
+
+thread_local! {
+    #[allow(clippy::missing_const_for_thread_local)]
+    static VALUES: RefCell<Option<std::vec::IntoIter<(String, String)>>> = RefCell::new(None);
+}
+
+/// Read the replay file
+fn load() -> std::vec::IntoIter<(String, String)> {
+    let path = std::env::var_os("VERIFIER_REPLAY_FILE")
+        .expect("VERIFIER: VERIFIER_REPLAY_FILE is not set");
+    let contents = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!("VERIFIER: unable to read replay file {:?}: {}", path, e)
+    });
+    contents
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| match l.rfind(" = ") {
+            Some(i) => (l[..i].trim().to_string(), l[i + 3..].trim().to_string()),
+            None => (String::new(), l.trim().to_string()),
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Return the next value from the replay file
+fn next_value<T: FromStr>(desc: &str) -> T {
+    let (name, value) = VALUES.with(|vs| {
+        vs.borrow_mut()
+            .get_or_insert_with(load)
+            .next()
+            .unwrap_or_else(|| panic!("VERIFIER: no replay value for '{}'", desc))
+    });
+    match value.parse() {
+        Ok(r) => r,
+        Err(_) => panic!("VERIFIER: unable to parse replay value '{} = {}' for '{}'", name, value, desc),
+    }
+}
+
+macro_rules! make_verifier_nondet {
+    ($typ:ident) => {
+        impl VerifierNonDet for $typ {
+            fn verifier_nondet(self) -> Self {
+                next_value(std::stringify!($typ))
+            }
+        }
+    };
+}
+
+make_verifier_nondet!(u8);
+make_verifier_nondet!(u16);
+make_verifier_nondet!(u32);
+make_verifier_nondet!(u64);
+make_verifier_nondet!(u128);
+make_verifier_nondet!(usize);
+
+make_verifier_nondet!(i8);
+make_verifier_nondet!(i16);
+make_verifier_nondet!(i32);
+make_verifier_nondet!(i64);
+make_verifier_nondet!(i128);
+make_verifier_nondet!(isize);
+
+make_verifier_nondet!(f32);
+make_verifier_nondet!(f64);
+
+make_verifier_nondet!(bool);
+
+/// Give the name `desc` to all unnamed symbolic values created by `f`.
+///
+/// Replay consumes values in order so this just calls `f`.
+pub fn with_name<R>(_desc: &'static str, f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+/// Create a vector of `n` bytes with replayed contents
+pub fn verifier_nondet_bytes(n: usize) -> Vec<u8> {
+    (0..n).map(|_| u8::verifier_nondet(0u8)).collect()
+}
+
+abstract_via_nondet!(u8, u16, u32, u64, u128, usize);
+abstract_via_nondet!(i8, i16, i32, i64, i128, isize);
+abstract_via_nondet!(f32, f64, bool);
+
+/// Assume that condition `cond` is true
+///
+/// When replaying, a false assumption means that the replayed
+/// values do not belong to the counterexample.
+pub fn assume(cond: bool) {
+    if !cond {
+        eprintln!("VERIFIER: ERROR: replayed values violate an assumption");
+        std::process::exit(1)
+    }
+}
+
+/// Reject the current execution with a verification failure.
+///
+/// In almost all circumstances, `report_error` should
+/// be used instead because it generates an error message.
+pub fn abort() -> ! {
+    std::process::abort()
+}
+
+/// Reject the current execution path with a verification success.
+/// This is equivalent to `assume(false)`
+/// and the opposite of `report_error(...)`.
+///
+/// Typical usage is in generating symbolic values when the value
+/// does not meet some criteria.
+pub fn reject() -> ! {
+    std::process::exit(0)
+}
+
+/// Detect whether the program is being replayed.
+///
+/// This is always true for this backend.
+pub fn is_replay() -> bool {
+    true
+}
+
+/// Describe what the replay backend supports
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        backend: Backend::Replay,
+        replay: true,
+        // Expected failures are not checked when replaying
+        expect: false,
+        floats: true,
+        wide_ints: true,
+        symbolic_sizes: true,
+    }
+}
+
+/// Reject the current execution with a verification failure
+/// and an error message.
+pub fn report_error(message: &str) -> ! {
+    eprintln!("VERIFIER: ERROR: {}", message);
+    abort();
+}
+
+/// Declare that failure is the expected behaviour
+pub fn expect_raw(msg: &str) {
+    eprintln!("VERIFIER_EXPECT: {}", msg)
+}
+
+/// Declare that failure is the expected behaviour
+pub fn expect(msg: Option<&str>) {
+    match msg {
+        None => eprintln!("VERIFIER_EXPECT: should_panic"),
+        Some(msg) => eprintln!("VERIFIER_EXPECT: should_panic(expected = \"{}\")", msg)
+    }
+}
+
+#[macro_export]
+macro_rules! assert {
+    ($cond:expr,) => { $crate::assert!($cond) };
+    ($cond:expr) => { $crate::assert!($cond, "assertion failed: {}", stringify!($cond)) };
+    ($cond:expr, $($arg:tt)+) => {{
+        if ! $cond {
+            let message = format!($($arg)+);
+            eprintln!("VERIFIER: panicked at '{}', {}:{}:{}",
+                      message,
+                      std::file!(), std::line!(), std::column!());
+            $crate::abort();
+        }
+    }}
+}
+
+#[macro_export]
+macro_rules! assert_eq {
+    ($left:expr, $right:expr,) => { $crate::assert_eq!($left, $right) };
+    ($left:expr, $right:expr) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left == right,
+            "assertion failed: `(left == right)` \
+             \n  left: `{:?}`,\n right: `{:?}`",
+            left,
+            right)
+    }};
+    ($left:expr, $right:expr, $fmt:tt $($arg:tt)*) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left == right,
+            concat!(
+                "assertion failed: `(left == right)` \
+                 \n  left: `{:?}`, \n right: `{:?}`: ", $fmt),
+            left, right $($arg)*);
+    }};
+}
+
+#[macro_export]
+macro_rules! assert_ne {
+    ($left:expr, $right:expr,) => { $crate::assert_ne!($left, $right) };
+    ($left:expr, $right:expr) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left != right,
+            "assertion failed: `(left != right)` \
+             \n  left: `{:?}`,\n right: `{:?}`",
+            left,
+            right)
+    }};
+    ($left:expr, $right:expr, $fmt:tt $($arg:tt)*) => {{
+        let left = $left;
+        let right = $right;
+        $crate::assert!(
+            left != right,
+            concat!(
+                "assertion failed: `(left != right)` \
+                 \n  left: `{:?}`, \n right: `{:?}`: ", $fmt),
+            left, right $($arg)*);
+    }};
+}
+
+/////////////////////////////////////////////////////////////////
+// End
+/////////////////////////////////////////////////////////////////
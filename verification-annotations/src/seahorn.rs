@@ -14,14 +14,17 @@ use std::default::Default;
 use core::panic::PanicInfo;
 
 pub use crate::traits::*;
+use crate::capabilities::{Backend, Capabilities};
 
 extern {
     fn __VERIFIER_error() -> !;
     fn __VERIFIER_assume(pred: i32);
 }
 
+/// Panic handler used by `rvt-patch-llvm --seahorn`
+/// (`info` is `None` for aborts).
 #[no_mangle]
-fn spanic(_info: &PanicInfo) -> ! {
+fn spanic(_info: Option<&PanicInfo>) -> ! {
     abort();
 }
 
@@ -53,13 +56,29 @@ pub fn reject() -> ! {
     panic!("Unreachable, should have been rejected!");
 }
 
-/// Detect whether the program is being run symbolically in KLEE
-/// or being replayed using the kleeRuntest runtime.
+/// Detect whether the program is being run symbolically in SeaHorn
+/// or being replayed using a counterexample harness generated by SeaHorn.
+///
+/// Replay is selected when building by setting the environment
+/// variable SEAHORN_CEX_HARNESS to the harness file.
 ///
 /// This is used to decide whether to display the values of
 /// variables that may be either symbolic or concrete.
 pub fn is_replay() -> bool {
-    panic!("SeaHorn doesn't support replay.")
+    cfg!(seahorn_replay)
+}
+
+/// Describe what the SeaHorn backend supports
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        backend: Backend::Seahorn,
+        replay: true,
+        // cargo-verify-seahorn.sh does not check expected failures
+        expect: false,
+        floats: true,
+        wide_ints: true,
+        symbolic_sizes: true,
+    }
 }
 
 /// Reject the current execution with a verification failure
@@ -100,39 +119,57 @@ make_nondet!(u8, __VERIFIER_nondet_u8, 0);
 make_nondet!(u16, __VERIFIER_nondet_u16, 0);
 make_nondet!(u32, __VERIFIER_nondet_u32, 0);
 make_nondet!(u64, __VERIFIER_nondet_u64, 0);
-// make_nondet!(u128, __VERIFIER_nondet_u128, 0);
 make_nondet!(usize, __VERIFIER_nondet_usize, 0);
 
 make_nondet!(i8, __VERIFIER_nondet_i8, 0);
 make_nondet!(i16, __VERIFIER_nondet_i16, 0);
 make_nondet!(i32, __VERIFIER_nondet_i32, 0);
 make_nondet!(i64, __VERIFIER_nondet_i64, 0);
-// make_nondet!(i128, __VERIFIER_nondet_i128, 0);
 make_nondet!(isize, __VERIFIER_nondet_isize, 0);
 
 make_nondet!(f32, __VERIFIER_nondet_f32, 0.0);
-make_nondet!(f64, __VERIFIER_nondet_f63, 0.0);
+make_nondet!(f64, __VERIFIER_nondet_f64, 0.0);
 
-impl VerifierNonDet for bool {
+// SeaHorn does not provide 128-bit non-deterministic values
+// so we combine two 64-bit values.
+impl VerifierNonDet for u128 {
     fn verifier_nondet(self) -> Self {
-        let c = u8::verifier_nondet(0u8);
-        assume(c == 0 || c == 1);
-        c == 1
+        let hi = u64::verifier_nondet(0u64) as u128;
+        let lo = u64::verifier_nondet(0u64) as u128;
+        (hi << 64) | lo
     }
 }
 
-impl <T: VerifierNonDet + Default> AbstractValue for T {
-    fn abstract_value() -> Self {
-        Self::verifier_nondet(Self::default())
+impl VerifierNonDet for i128 {
+    fn verifier_nondet(self) -> Self {
+        u128::verifier_nondet(0u128) as i128
     }
 }
 
-impl <T: VerifierNonDet + Default> Symbolic for T {
-    fn symbolic(_desc: &'static str) -> Self {
-        Self::verifier_nondet(Self::default())
+/// Give the name `desc` to all unnamed symbolic values created by `f`.
+///
+/// SeaHorn does not name its values so this just calls `f`.
+pub fn with_name<R>(_desc: &'static str, f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+/// Create a vector of `n` bytes with non-deterministic contents
+pub fn verifier_nondet_bytes(n: usize) -> Vec<u8> {
+    (0..n).map(|_| u8::verifier_nondet(0u8)).collect()
+}
+
+impl VerifierNonDet for bool {
+    fn verifier_nondet(self) -> Self {
+        let c = u8::verifier_nondet(0u8);
+        assume(c == 0 || c == 1);
+        c == 1
     }
 }
 
+abstract_via_nondet!(u8, u16, u32, u64, u128, usize);
+abstract_via_nondet!(i8, i16, i32, i64, i128, isize);
+abstract_via_nondet!(f32, f64, bool);
+
 #[macro_export]
 macro_rules! assert {
     ($cond:expr,) => { $crate::assert!($cond) };
@@ -150,6 +187,7 @@ macro_rules! assert {
 
 #[macro_export]
 macro_rules! assert_eq {
+    ($left:expr, $right:expr,) => { $crate::assert_eq!($left, $right) };
     ($left:expr, $right:expr) => {{
         let left = $left;
         let right = $right;
@@ -174,6 +212,7 @@ macro_rules! assert_eq {
 
 #[macro_export]
 macro_rules! assert_ne {
+    ($left:expr, $right:expr,) => { $crate::assert_ne!($left, $right) };
     ($left:expr, $right:expr) => {{
         let left = $left;
         let right = $right;
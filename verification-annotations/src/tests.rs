@@ -14,7 +14,6 @@ fn t0() {
     verifier::assume(4 <= a && a <= 7);
     verifier::assume(5 <= b && b <= 8);
 
-    #[cfg(not(any(feature = "verifier-crux", feature = "verifier-seahorn")))]
     if verifier::is_replay() { eprintln!("Test values: a = {}, b = {}", a, b) }
 
     let r = a*b;
@@ -35,6 +34,7 @@ fn t1() {
 #[cfg_attr(not(feature = "verifier-crux"), test)]
 #[cfg_attr(feature = "verifier-crux", crux_test)]
 fn t2() {
+    // Crux-MIR reports overflow itself so the expectation cannot be checked
     #[cfg(not(feature = "verifier-crux"))]
     verifier::expect(Some("multiply with overflow"));
 
@@ -49,7 +49,6 @@ fn t2() {
 #[cfg_attr(not(feature = "verifier-crux"), test)]
 #[cfg_attr(feature = "verifier-crux", crux_test)]
 fn t3() {
-    #[cfg(not(feature = "verifier-crux"))]
     verifier::expect(Some("assertion failed"));
 
     let a : u32 = verifier::AbstractValue::abstract_value();
@@ -63,7 +62,6 @@ fn t3() {
 #[cfg_attr(not(feature = "verifier-crux"), test)]
 #[cfg_attr(feature = "verifier-crux", crux_test)]
 fn t4() {
-    #[cfg(not(feature = "verifier-crux"))]
     verifier::expect(None);
 
     let a : u32 = verifier::AbstractValue::abstract_value();
@@ -85,6 +83,375 @@ fn t5() {
     verifier::assert_ne!(a, a+1);
 }
 
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn t6() {
+    use std::io::Read;
+
+    verifier::io::symbolic_stdin(4);
+    verifier::io::symbolic_file("input.txt", 8);
+
+    let mut line = String::new();
+    let n = verifier::io::stdin().read_line(&mut line).unwrap_or(0);
+    assert!(n <= 4);
+
+    let mut contents = Vec::new();
+    let mut file = verifier::io::File::open("input.txt").unwrap();
+    let len = file.read_to_end(&mut contents).unwrap();
+    assert!(len <= 8);
+    assert!(len as u64 == file.len());
+
+    assert!(verifier::io::File::open("missing.txt").is_err());
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn stdin_lines() {
+    use std::io::BufRead;
+
+    verifier::io::symbolic_stdin(4);
+
+    let mut first = String::new();
+    let n = verifier::io::stdin().lock().read_line(&mut first).unwrap_or(0);
+    assert!(n <= 4);
+
+    // The lock returns the data that it did not read
+    let mut count = 0;
+    let mut len = n;
+    for line in verifier::io::stdin().lock().lines() {
+        // (Symbolic input is not always valid UTF-8.)
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        len += line.len() + 1;
+        count += 1;
+    }
+    assert!(count <= 3);
+    assert!(len <= 5);
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn t7() {
+    verifier::symbolic_env(&["VERIFIER_TEST_VAR"], 2);
+    match std::env::var("VERIFIER_TEST_VAR") {
+        Ok(value) => assert!(value.len() <= 2),
+        Err(std::env::VarError::NotPresent) => (),
+        Err(std::env::VarError::NotUnicode(value)) => assert!(value.len() <= 2),
+    }
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn t8() {
+    let a : u32 = verifier::AbstractValue::abstract_value();
+    verifier::assume(a <= 1000000); // avoid overflow
+    assert!(a < a + 1, "a = {}", a);
+    verifier::assert_eq!(a + 1, 1 + a, "a = {}", a);
+    verifier::assert_ne!(a, a + 1, "a = {}", a);
+    verifier::assert_eq!(a, a,);
+    verifier::assert_ne!(a, a + 1,);
+}
+
+////////////////////////////////////////////////////////////////
+// Non-deterministic values of every primitive type
+////////////////////////////////////////////////////////////////
+
+macro_rules! nondet_int_test {
+    ($name:ident, $typ:ty) => {
+        #[cfg_attr(not(feature = "verifier-crux"), test)]
+        #[cfg_attr(feature = "verifier-crux", crux_test)]
+        fn $name() {
+            let a : $typ = verifier::AbstractValue::abstract_value();
+            let b : $typ = verifier::AbstractValue::abstract_value();
+            verifier::assume(a < b);
+            assert!(a != <$typ>::MAX);
+            assert!(b != <$typ>::MIN);
+        }
+    };
+}
+
+nondet_int_test!(nondet_u8, u8);
+nondet_int_test!(nondet_u16, u16);
+nondet_int_test!(nondet_u32, u32);
+nondet_int_test!(nondet_u64, u64);
+nondet_int_test!(nondet_u128, u128);
+nondet_int_test!(nondet_usize, usize);
+nondet_int_test!(nondet_i8, i8);
+nondet_int_test!(nondet_i16, i16);
+nondet_int_test!(nondet_i32, i32);
+nondet_int_test!(nondet_i64, i64);
+nondet_int_test!(nondet_i128, i128);
+nondet_int_test!(nondet_isize, isize);
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn nondet_wide() {
+    // check that all bits of 128-bit values are non-deterministic
+    let a : u128 = verifier::AbstractValue::abstract_value();
+    verifier::assume(a >> 64 == 1);
+    assert!(a > u64::MAX as u128);
+
+    let b : i128 = verifier::AbstractValue::abstract_value();
+    verifier::assume(b < i64::MIN as i128);
+    assert!(b as i64 as i128 != b);
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn nondet_bool() {
+    let a : bool = verifier::AbstractValue::abstract_value();
+    let b : bool = verifier::AbstractValue::abstract_value();
+    verifier::assume(a != b);
+    assert!(a ^ b);
+}
+
+macro_rules! nondet_float_test {
+    ($name:ident, $typ:ty) => {
+        #[cfg_attr(not(feature = "verifier-crux"), test)]
+        #[cfg_attr(feature = "verifier-crux", crux_test)]
+        fn $name() {
+            let a : $typ = verifier::AbstractValue::abstract_value();
+            let b : $typ = verifier::AbstractValue::abstract_value();
+            verifier::assume(a < b);
+            assert!(!a.is_nan() && !b.is_nan());
+            assert!(a != b);
+        }
+    };
+}
+
+nondet_float_test!(nondet_f32, f32);
+nondet_float_test!(nondet_f64, f64);
+
+////////////////////////////////////////////////////////////////
+// Derived instances of AbstractValue and Symbolic
+////////////////////////////////////////////////////////////////
+
+#[derive(verifier::AbstractValue, verifier::Symbolic)]
+struct Interval {
+    #[verifier(assume = "lo <= hi")]
+    lo: u32,
+    hi: u32,
+}
+
+#[derive(verifier::AbstractValue, verifier::Symbolic)]
+struct Small(#[verifier(assume = "_0 < 10")] u8, bool);
+
+#[derive(verifier::AbstractValue, verifier::Symbolic)]
+enum Shape {
+    Empty,
+    Point(Small),
+    Line { i: Interval },
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn derived() {
+    let i : Interval = verifier::AbstractValue::abstract_value();
+    assert!(i.lo <= i.hi);
+
+    let s : Small = verifier::Symbolic::symbolic("s");
+    assert!(s.0 < 10);
+
+    let shape : Shape = verifier::AbstractValue::abstract_value();
+    match shape {
+        Shape::Empty => (),
+        Shape::Point(Small(x, flag)) => assert!(x < 10 || flag),
+        Shape::Line { i } => assert!(i.hi - i.lo <= i.hi),
+    }
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn composite() {
+    let (a, b) : (u8, Option<u32>) = verifier::AbstractValue::abstract_value();
+    if let Some(b) = b {
+        verifier::assume(b < 100);
+        assert!(u32::from(a) + b < 356);
+    }
+
+    let r : Result<u16, i8> = verifier::Symbolic::symbolic("r");
+    match r {
+        Ok(x) => assert!(u32::from(x) <= 0xffff),
+        Err(e) => assert!(i32::from(e) >= -128),
+    }
+
+    let xs : [u8; 4] = verifier::AbstractValue::abstract_value();
+    for x in xs.iter() {
+        verifier::assume(*x < 10);
+    }
+    assert!(xs.iter().map(|x| u32::from(*x)).sum::<u32>() < 40);
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn named() {
+    let a : u32 = verifier::Symbolic::symbolic("a");
+    let (b, c) : (u32, bool) = verifier::with_name("bc", verifier::AbstractValue::abstract_value);
+    verifier::assume(a < 10 && b < 10);
+    assert!(a + b < 20 || c);
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn capabilities() {
+    let caps = verifier::capabilities();
+    if !caps.replay {
+        assert!(!verifier::is_replay());
+    }
+    if caps.backend == verifier::Backend::Replay {
+        assert!(verifier::is_replay());
+    }
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn quantifiers() {
+    let xs : [u8; 4] = verifier::AbstractValue::abstract_value();
+    for x in xs.iter() {
+        verifier::assume(*x < 10);
+    }
+    verifier::assert_forall(0..xs.len(), |i| xs[i] < 10);
+    verifier::assert_forall(2..2, |i: usize| xs[i] > 100);
+
+    verifier::assume_exists(0..xs.len(), |i| xs[i] == 0);
+    assert!(xs.contains(&0));
+}
+
+#[cfg(feature = "verifier-seahorn")]
+#[test]
+fn quantifiers_seahorn() {
+    let n : usize = verifier::AbstractValue::abstract_value();
+    verifier::assume(n <= 64);
+    let xs : Vec<u8> = (0..n).map(|_| verifier::AbstractValue::abstract_value()).collect();
+
+    verifier::assume_forall(0..n, |i| xs[i] < 10);
+    verifier::assert_forall(0..n, |i| xs[i] < 10);
+
+    verifier::assume_exists(0..n, |i| xs[i] == 0);
+    verifier::assert_exists(0..n, |i| xs[i] < 10);
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn loops() {
+    let n : u32 = verifier::AbstractValue::abstract_value();
+    verifier::assume(n <= 4);
+
+    let mut i = 0;
+    let mut sum = 0;
+    let mut outer = None;
+    while i < n {
+        verifier::invariant!(i < n && sum <= 3 * i);
+        verifier::decreases!(outer, n - i);
+        // The inner loop is run on every iteration of the outer loop
+        let mut j = 0;
+        let mut inner = None;
+        while j < 3 {
+            verifier::invariant!(j < 3);
+            verifier::decreases!(inner, 3 - j);
+            sum += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    assert!(sum == 3 * n);
+}
+
+fn count_down(n: i32) -> i32 {
+    let mut i = n;
+    let mut steps = 0;
+    let mut variant = None;
+    while i > 0 {
+        verifier::decreases!(variant, i);
+        i -= 1;
+        steps += 1;
+    }
+    steps
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn loops_called_twice() {
+    let n : i32 = verifier::AbstractValue::abstract_value();
+    verifier::assume((0..=3).contains(&n));
+    // The variant starts again on every call
+    assert!(count_down(n) == n);
+    assert!(count_down(n) == n);
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn loops_negative_variant() {
+    verifier::expect(Some("loop variant is negative"));
+
+    let mut i : i32 = -2;
+    let mut variant = None;
+    while i < 0 {
+        verifier::decreases!(variant, i);
+        i -= 1;
+    }
+}
+
+#[verifier::requires(x < 100)]
+#[verifier::ensures(|r| r > x && r <= 100)]
+fn increment(x: u32) -> u32 {
+    if x == 0 {
+        return 1;
+    }
+    x + 1
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn contracts() {
+    let x : u32 = verifier::AbstractValue::abstract_value();
+    verifier::assume(x < 50);
+    let y = increment(x);
+    assert!(x < y);
+}
+
+// The message of a failing condition contains the braces of the condition
+#[verifier::requires(x < { 100 })]
+#[verifier::ensures(|r| r == { x * 2 })]
+fn double(x: u32) -> u32 {
+    x * 2
+}
+
+// Postconditions that name the result do not require `Clone`
+// (and `crate = "..."` gives the path of this crate like a crate
+// that uses it through propverify would)
+#[derive(verifier::AbstractValue)]
+#[verifier(crate = "crate")]
+struct Counter {
+    count: u32,
+}
+
+#[verifier::ensures(crate = "crate", |c| c.count == n)]
+fn counter(n: u32) -> Counter {
+    Counter { count: n }
+}
+
+// (The `contracts` feature cannot replace functions that return `impl Trait`.)
+#[cfg(not(feature = "contracts"))]
+#[verifier::requires(k < 100)]
+#[verifier::ensures(|f| f(1) == k + 1)]
+fn adder(k: u32) -> impl Fn(u32) -> u32 {
+    move |x| x + k
+}
+
+#[cfg_attr(not(feature = "verifier-crux"), test)]
+#[cfg_attr(feature = "verifier-crux", crux_test)]
+fn contracts_results() {
+    let x : u32 = verifier::AbstractValue::abstract_value();
+    verifier::assume(x < 50);
+    assert!(double(x) == x + x);
+    assert!(counter(x).count == x);
+    #[cfg(not(feature = "contracts"))]
+    assert!(adder(x)(2) == x + 2);
+}
+
 ////////////////////////////////////////////////////////////////
 // End
 ////////////////////////////////////////////////////////////////
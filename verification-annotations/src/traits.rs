@@ -50,6 +50,194 @@ pub trait Symbolic: Sized {
     }
 }
 
+/// Implement `AbstractValue` and `Symbolic` for primitive types
+/// in terms of `VerifierNonDet`.
+///
+/// Backends use this for each primitive type instead of a blanket
+/// implementation so that composite types (below) can have their own
+/// implementations.
+/// (The KLEE and Crux-MIR backends do not use this because they
+/// pass `desc` on to the verifier.)
+#[allow(unused_macros)]
+macro_rules! abstract_via_nondet {
+    ($($typ:ty),*) => {
+        $(
+            impl AbstractValue for $typ {
+                fn abstract_value() -> Self {
+                    Self::verifier_nondet(Self::default())
+                }
+            }
+
+            impl Symbolic for $typ {
+                fn symbolic(_desc: &'static str) -> Self {
+                    Self::verifier_nondet(Self::default())
+                }
+            }
+        )*
+    };
+}
+
+/////////////////////////////////////////////////////////////////
+// Composite types
+//
+// These are built from their components (so they work with
+// every backend) and `VerifierNonDet` is implemented using
+// `AbstractValue` because the argument is ignored anyway.
+/////////////////////////////////////////////////////////////////
+
+impl<T: AbstractValue> AbstractValue for Option<T> {
+    fn abstract_value() -> Self {
+        if bool::abstract_value() {
+            Some(T::abstract_value())
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Symbolic> Symbolic for Option<T> {
+    fn symbolic(desc: &'static str) -> Self {
+        if bool::symbolic(desc) {
+            Some(T::symbolic(desc))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: AbstractValue> VerifierNonDet for Option<T> {
+    fn verifier_nondet(self) -> Self {
+        Self::abstract_value()
+    }
+}
+
+impl<T: AbstractValue, E: AbstractValue> AbstractValue for Result<T, E> {
+    fn abstract_value() -> Self {
+        if bool::abstract_value() {
+            Ok(T::abstract_value())
+        } else {
+            Err(E::abstract_value())
+        }
+    }
+}
+
+impl<T: Symbolic, E: Symbolic> Symbolic for Result<T, E> {
+    fn symbolic(desc: &'static str) -> Self {
+        if bool::symbolic(desc) {
+            Ok(T::symbolic(desc))
+        } else {
+            Err(E::symbolic(desc))
+        }
+    }
+}
+
+impl<T: AbstractValue, E: AbstractValue> VerifierNonDet for Result<T, E> {
+    fn verifier_nondet(self) -> Self {
+        Self::abstract_value()
+    }
+}
+
+/// Repeat an expression once for each token.
+macro_rules! replace_expr {
+    ($_t:tt $sub:expr) => {
+        $sub
+    };
+}
+
+/// Implement the traits for arrays of length `$n`
+/// (one token per element).
+///
+/// Arrays up to length 32 are supported.
+macro_rules! array_instances {
+    ($n:literal: $($t:tt)*) => {
+        impl<T: AbstractValue> AbstractValue for [T; $n] {
+            fn abstract_value() -> Self {
+                [$(replace_expr!($t T::abstract_value())),*]
+            }
+        }
+
+        impl<T: Symbolic> Symbolic for [T; $n] {
+            fn symbolic(desc: &'static str) -> Self {
+                [$(replace_expr!($t T::symbolic(desc))),*]
+            }
+        }
+
+        impl<T: AbstractValue> VerifierNonDet for [T; $n] {
+            fn verifier_nondet(self) -> Self {
+                Self::abstract_value()
+            }
+        }
+    };
+}
+
+array_instances!(1: _);
+array_instances!(2: _ _);
+array_instances!(3: _ _ _);
+array_instances!(4: _ _ _ _);
+array_instances!(5: _ _ _ _ _);
+array_instances!(6: _ _ _ _ _ _);
+array_instances!(7: _ _ _ _ _ _ _);
+array_instances!(8: _ _ _ _ _ _ _ _);
+array_instances!(9: _ _ _ _ _ _ _ _ _);
+array_instances!(10: _ _ _ _ _ _ _ _ _ _);
+array_instances!(11: _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(12: _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(13: _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(14: _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(15: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(16: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(17: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(18: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(19: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(20: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(21: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(22: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(23: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(24: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(25: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(26: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(27: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(28: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(29: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(30: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(31: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+array_instances!(32: _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _);
+
+macro_rules! tuple_instances {
+    ($($t:ident),*) => {
+        impl<$($t: AbstractValue),*> AbstractValue for ($($t,)*) {
+            fn abstract_value() -> Self {
+                ($($t::abstract_value(),)*)
+            }
+        }
+
+        impl<$($t: Symbolic),*> Symbolic for ($($t,)*) {
+            fn symbolic(desc: &'static str) -> Self {
+                ($($t::symbolic(desc),)*)
+            }
+        }
+
+        impl<$($t: AbstractValue),*> VerifierNonDet for ($($t,)*) {
+            fn verifier_nondet(self) -> Self {
+                Self::abstract_value()
+            }
+        }
+    };
+}
+
+tuple_instances!(A);
+tuple_instances!(A, B);
+tuple_instances!(A, B, C);
+tuple_instances!(A, B, C, D);
+tuple_instances!(A, B, C, D, E);
+tuple_instances!(A, B, C, D, E, F);
+tuple_instances!(A, B, C, D, E, F, G);
+tuple_instances!(A, B, C, D, E, F, G, H);
+tuple_instances!(A, B, C, D, E, F, G, H, I);
+tuple_instances!(A, B, C, D, E, F, G, H, I, J);
+tuple_instances!(A, B, C, D, E, F, G, H, I, J, K);
+tuple_instances!(A, B, C, D, E, F, G, H, I, J, K, L);
+
 /////////////////////////////////////////////////////////////////
 // End
 /////////////////////////////////////////////////////////////////